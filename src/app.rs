@@ -1,48 +1,193 @@
+mod config;
+mod contest;
 mod fetch_test_suite;
+mod init;
 mod login;
+mod logout;
 mod test;
 
 use crate::{
-    cli::{Cli, Command},
+    cli::{self, Cli, Command, ConfigCommand},
     dao::{self, Dao},
+    domain::path::{self, SessionDataPath, DEFAULT_PROFILE},
     dto::{config::Config, SessionData},
-    handler::{file_handler, http_handler::HttpHandler},
+    error::UnwrapOrExit,
+    handler::{
+        cache_handler::ResponseCache,
+        config_handler, file_handler,
+        http_handler::{self, HttpHandler},
+        session_handler::{self, SessionStore},
+    },
 };
 use clap::Parser;
-use ureq::Agent;
+use cookie_store::Cookie;
+use std::{iter, path::PathBuf};
 
-pub fn run(config: Config) {
-    match Cli::parse().command {
-        Command::Login { check } => login::run(&config, check),
+pub fn run() {
+    let cli = Cli::parse();
+
+    // Runs before loading the config, which requires `.atcoder`
+    if let Command::Init = cli.command {
+        let app_config = config_handler::load_app_config().unwrap_or_exit();
+        init::run(&app_config);
+        return;
+    }
+
+    // Runs before loading the config, which fails on the first error
+    if let Command::Config {
+        command: ConfigCommand::Check,
+    } = cli.command
+    {
+        let app_config = config_handler::load_app_config().unwrap_or_exit();
+        config::check(&app_config);
+        return;
+    }
+
+    let mut config = config_handler::load_config().unwrap_or_exit();
+    config.user_config.http.offline = cli.offline;
+    config.user_config.http.record = cli.record;
+    config.user_config.http.replay = cli.replay;
+    if let Some(profile) = cli.profile {
+        config.user_config.session.profile = Some(profile);
+    }
+    check_profile(&config).unwrap_or_exit();
+
+    match cli.command {
+        Command::Login {
+            check,
+            format,
+            username,
+            password_stdin,
+            import,
+        } => login::run(&config, check, format, username, password_stdin, import),
+        Command::Init => unreachable!(),
+        Command::Config { command } => match command {
+            ConfigCommand::Check => unreachable!(),
+            ConfigCommand::Show => config::show(&config),
+        },
+        Command::Logout => logout::run(&config),
         Command::FetchTestSuite { url } => fetch_test_suite::run(&config, url),
+        Command::Contest { contest, format } => contest::run(&config, contest, format),
         Command::Test {
-            language,
-            task,
+            language_and_task,
             test_cases,
             verbose,
+            rebuild,
+            time_limit,
+            format,
+            diff_style,
         } => {
-            test::run(&config, language, task, test_cases, verbose);
+            let (language, task) = cli::split_language_and_task(language_and_task);
+            let options = test::Options {
+                test_cases,
+                verbose,
+                rebuild,
+                time_limit,
+                format,
+                diff_style,
+            };
+            test::run(&config, language, task, options);
         }
     }
 }
 
 fn setup_dao_with_fetching(config: &Config) -> Result<Dao, dao::Error> {
-    let http_handler = HttpHandler::new(Agent::new());
+    setup_dao_with_cookies(config, Vec::new())
+}
+
+fn setup_dao_with_cookies(
+    config: &Config,
+    cookies: Vec<Cookie<'static>>,
+) -> Result<Dao, dao::Error> {
+    let http_handler = HttpHandler::with_cookies(cookies, &config.user_config.http)?
+        .with_cache(response_cache(config));
     let csrf_token = Dao::fetch_csrf_token(&http_handler, &config.app_config.url.homepage)?;
     Ok(Dao::new(http_handler, csrf_token))
 }
 
-fn setup_dao_with_loading(config: &Config) -> Result<Dao, file_handler::Error> {
+fn setup_dao_with_loading(config: &Config) -> Result<Dao, Error> {
     let SessionData {
         cookies,
         csrf_token,
-    } = file_handler::load(&config.app_config.path.session_data)?;
+        username,
+    } = session_store(config).load()?;
 
-    let http_handler = HttpHandler::with_cookies(cookies);
-    Ok(Dao::new(http_handler, csrf_token))
+    let http_handler = HttpHandler::with_cookies(cookies, &config.user_config.http)?
+        .with_cache(response_cache(config));
+    Ok(Dao::new(http_handler, csrf_token).with_username(username))
+}
+
+/// Cache of each profile, since pages differ by the logged-in user
+fn response_cache(config: &Config) -> ResponseCache {
+    ResponseCache::new(config.app_config.path.cache.join(profile(config)))
 }
 
-fn save_dao(config: &Config, dao: Dao) -> Result<(), file_handler::Error> {
+fn profile(config: &Config) -> &str {
+    config
+        .user_config
+        .session
+        .profile
+        .as_deref()
+        .unwrap_or(DEFAULT_PROFILE)
+}
+
+/// The profile may also be set in the config, and is put into file names
+fn check_profile(config: &Config) -> Result<(), Error> {
+    let profile = profile(config);
+    if path::is_valid_profile(profile) {
+        Ok(())
+    } else {
+        Err(Error::InvalidProfile(profile.to_string()))
+    }
+}
+
+fn session_store(config: &Config) -> Box<dyn SessionStore> {
+    profile_session_store(config, profile(config))
+}
+
+fn profile_session_store(config: &Config, profile: &str) -> Box<dyn SessionStore> {
+    let path = session_data_path(config, profile);
+    session_handler::session_store(&config.user_config.session, &path, profile)
+}
+
+/// Session files in order of precedence: the project one, then the per-user one shared across projects
+fn session_data_paths(config: &Config) -> Vec<SessionDataPath> {
+    let project = config.app_config.path.session_data.clone();
+    let global =
+        file_handler::data_home().map(|dir| dir.join(&config.app_config.path.global_session_data));
+    iter::once(project)
+        .chain(global)
+        .map(SessionDataPath::new)
+        .collect()
+}
+
+fn session_data_path(config: &Config, profile: &str) -> PathBuf {
+    let mut paths = session_data_paths(config)
+        .iter()
+        .map(|path| path.profile_file(profile))
+        .collect::<Vec<_>>();
+
+    match paths.iter().position(|path| path.exists()) {
+        Some(i) => paths.swap_remove(i),
+        // New sessions go to the per-user location only if configured
+        None if config.user_config.session.global => paths.pop().unwrap(),
+        None => paths.swap_remove(0),
+    }
+}
+
+fn save_dao(config: &Config, dao: Dao) -> Result<(), session_handler::Error> {
     let session_data = dao.into_session_data();
-    file_handler::save(&config.app_config.path.session_data, &session_data)
+    session_store(config).save(&session_data)
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    SessionHandler(#[from] session_handler::Error),
+
+    #[error(transparent)]
+    HttpHandler(#[from] http_handler::Error),
+
+    #[error("Invalid Profile: {0}, which must not contain `/`, `\\` or `.`")]
+    InvalidProfile(String),
 }
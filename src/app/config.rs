@@ -0,0 +1,38 @@
+use crate::{
+    dto::{
+        config::{AppConfig, Config},
+        Severity,
+    },
+    error::UnwrapOrExit,
+    handler::{config_handler, terminal_handler},
+};
+use std::process;
+
+pub fn check(app_config: &AppConfig) {
+    config_handler::enter_workspace(app_config).unwrap_or_exit();
+    let paths = config_handler::user_config_paths(app_config);
+    let diagnostics = config_handler::check_user_config(&paths).unwrap_or_exit();
+
+    terminal_handler::print_diagnostics(&diagnostics);
+
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .count();
+    if errors > 0 {
+        eprintln!("{errors} error(s) found");
+        process::exit(1);
+    }
+    println!("Config OK");
+}
+
+pub fn show(config: &Config) {
+    for path in config_handler::user_config_paths(&config.app_config) {
+        let state = if path.exists() { "found" } else { "not found" };
+        println!("# {} ({state})", path.display());
+    }
+    println!();
+
+    let user_config = toml::to_string_pretty(&config.user_config).unwrap_or_exit();
+    print!("{user_config}");
+}
@@ -0,0 +1,65 @@
+use super::{response_cache, setup_dao_with_loading};
+use crate::{
+    cli::OutputFormat,
+    dao::{self, Dao},
+    dto::{config::Config, ContestInfo},
+    error::UnwrapOrExit,
+    handler::{
+        http_handler::{self, HttpHandler},
+        terminal_handler,
+    },
+};
+use time::{OffsetDateTime, UtcOffset};
+
+pub fn run(config: &Config, contest: String, format: OutputFormat) {
+    let dao = setup(config);
+    let contest_info = fetch(config, &dao, &contest).unwrap_or_exit();
+    print(&contest_info, format).unwrap_or_exit();
+}
+
+fn setup(config: &Config) -> Dao {
+    // Past contests can be viewed without logging in
+    setup_dao_with_loading(config)
+        .or_else(|_| {
+            let http_handler =
+                HttpHandler::new(&config.user_config.http)?.with_cache(response_cache(config));
+            Ok::<_, http_handler::Error>(Dao::new(http_handler, String::new()))
+        })
+        .unwrap_or_exit()
+}
+
+fn fetch(config: &Config, dao: &Dao, contest: &str) -> Result<ContestInfo, Error> {
+    let contest_url = config.app_config.url.contests.contest_url(contest);
+    let contest_info = dao.fetch_contest_info(&contest_url)?;
+
+    Ok(ContestInfo {
+        start_time: to_local(contest_info.start_time),
+        end_time: to_local(contest_info.end_time),
+        ..contest_info
+    })
+}
+
+fn to_local(datetime: OffsetDateTime) -> OffsetDateTime {
+    UtcOffset::current_local_offset().map_or(datetime, |offset| datetime.to_offset(offset))
+}
+
+fn print(contest_info: &ContestInfo, format: OutputFormat) -> Result<(), Error> {
+    match format {
+        OutputFormat::Table => terminal_handler::print_contest_info(contest_info)?,
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(contest_info)?),
+    }
+
+    Ok(())
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Dao(#[from] dao::Error),
+
+    #[error(transparent)]
+    TerminalHandler(#[from] terminal_handler::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
@@ -1,33 +1,41 @@
-use super::save_dao;
+use super::{login, save_dao, setup_dao_with_loading};
 use crate::{
     dao::{self, Dao},
     domain::{
         page_type::ContestHome,
         url::{self, FetchTaskUrl, Url},
     },
-    dto::{config::Config, SessionData, TaskInfo},
+    dto::{config::Config, TaskInfo},
     error::UnwrapOrExit,
-    handler::{file_handler, http_handler::HttpHandler},
+    handler::{file_handler, session_handler},
 };
 use itertools::Itertools;
 
 pub fn run(config: &Config, task_url: FetchTaskUrl) {
     let dao = setup(config);
-    fetch(config, &dao, task_url).unwrap_or_exit();
+    let dao = fetch_with_relogin(config, dao, &task_url).unwrap_or_exit();
     save_dao(config, dao).unwrap_or_exit();
 }
 
 fn setup(config: &Config) -> Dao {
-    let SessionData {
-        cookies,
-        csrf_token,
-    } = file_handler::load(&config.app_config.path.session_data).unwrap_or_exit();
+    setup_dao_with_loading(config).unwrap_or_exit()
+}
 
-    let http_handler = HttpHandler::with_cookies(cookies);
-    Dao::new(http_handler, csrf_token)
+/// Retries once after logging in again if the session has expired
+fn fetch_with_relogin(config: &Config, dao: Dao, task_url: &FetchTaskUrl) -> Result<Dao, Error> {
+    match fetch(config, &dao, task_url) {
+        Err(Error::Dao(dao::Error::NotLoggedIn)) => {
+            let Some(dao) = login::relogin(config)? else {
+                return Err(dao::Error::NotLoggedIn.into());
+            };
+            fetch(config, &dao, task_url)?;
+            Ok(dao)
+        }
+        result => result.map(|()| dao),
+    }
 }
 
-fn fetch(config: &Config, dao: &Dao, task_url: FetchTaskUrl) -> Result<(), Error> {
+fn fetch(config: &Config, dao: &Dao, task_url: &FetchTaskUrl) -> Result<(), Error> {
     let test_suite = dao.fetch_test_suite(task_url.task_url())?;
     file_handler::save_test_suite(&config.app_config.path.test, &test_suite)?;
 
@@ -38,7 +46,7 @@ fn fetch(config: &Config, dao: &Dao, task_url: FetchTaskUrl) -> Result<(), Error
 
     println!("Saved: {task_names:?}");
 
-    let task_screen_names = fetch_task_screen_names(dao, &task_url)?;
+    let task_screen_names = fetch_task_screen_names(dao, task_url)?;
     let task_info = create_task_info(task_names, task_screen_names, task_url.contest_url());
     file_handler::save(&config.app_config.path.tasks_info, &task_info)?;
 
@@ -80,21 +88,27 @@ pub enum Error {
     #[error(transparent)]
     FileHandler(#[from] file_handler::Error),
 
+    #[error(transparent)]
+    SessionHandler(#[from] session_handler::Error),
+
     #[error(transparent)]
     InvalidUrl(#[from] url::Error),
+
+    #[error(transparent)]
+    Login(#[from] login::Error),
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::handler::http_handler::HttpHandler;
-    use ureq::Agent;
+    use crate::utils::test::FixtureTransport;
 
     #[test]
-    #[ignore]
     fn test_fetch_task_screen_names() {
         // Setup
-        let http_handler = HttpHandler::new(Agent::new());
+        let http_handler = FixtureTransport::new()
+            .route_get("https://atcoder.jp/contests/abc388/tasks", "tasks.html")
+            .into_http_handler();
         let dao = Dao::new(http_handler, "Dummy CSRF Token".to_string());
 
         let task_url = "https://atcoder.jp/contests/abc388".parse().unwrap();
@@ -104,6 +118,6 @@ mod tests {
 
         // Verify
         println!("{:#?}", tasks_info);
-        assert_eq!(7, tasks_info.len());
+        assert_eq!(2, tasks_info.len());
     }
 }
@@ -0,0 +1,77 @@
+use crate::{
+    domain::path::SessionDataPath,
+    dto::config::AppConfig,
+    error::UnwrapOrExit,
+    handler::{config_handler::USER_CONFIG_TEMPLATE, file_handler},
+};
+use std::path::{Path, PathBuf};
+
+const GITIGNORE: &str = ".gitignore";
+
+pub fn run(app_config: &AppConfig) {
+    init(Path::new(""), app_config).unwrap_or_exit();
+}
+
+fn init(root: &Path, app_config: &AppConfig) -> Result<(), Error> {
+    let user_config = root.join(&app_config.path.user_config);
+    if user_config.exists() {
+        return Err(Error::AlreadyInitialized(user_config));
+    }
+
+    file_handler::save_text(&user_config, USER_CONFIG_TEMPLATE)?;
+    println!("{} Created", user_config.display());
+
+    // Sessions and cached pages, which have the CSRF token and the username, are kept out of git
+    let gitignore = root.join(GITIGNORE);
+    let patterns = [
+        SessionDataPath::new(&app_config.path.session_data).gitignore_pattern(),
+        format!("/{}/", app_config.path.cache.display()),
+    ];
+    for pattern in patterns {
+        if file_handler::append_line(&gitignore, &pattern)? {
+            println!("{pattern} Added to {}", gitignore.display());
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    FileHandler(#[from] file_handler::Error),
+
+    #[error("Already Initialized: {} exists", .0.display())]
+    AlreadyInitialized(PathBuf),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{handler::config_handler, utils::test::temp_dir};
+    use std::fs;
+
+    #[test]
+    fn test_init() {
+        // Setup
+        let root = &temp_dir("init");
+        fs::write(root.join(GITIGNORE), "/target").unwrap();
+        let app_config = config_handler::load_app_config().unwrap();
+
+        // Run
+        let first = init(root, &app_config);
+        let second = init(root, &app_config);
+
+        // Verify
+        assert!(first.is_ok());
+        assert!(matches!(second, Err(Error::AlreadyInitialized(_))));
+        assert_eq!(
+            USER_CONFIG_TEMPLATE,
+            fs::read_to_string(root.join(&app_config.path.user_config)).unwrap()
+        );
+        assert_eq!(
+            "/target\n/.atcoder/session_data*.json\n/.atcoder/cache/\n",
+            fs::read_to_string(root.join(GITIGNORE)).unwrap()
+        );
+    }
+}
@@ -1,61 +1,260 @@
-use super::{setup_dao_with_fetching, setup_dao_with_loading};
+use super::{
+    profile, profile_session_store, save_dao, session_data_path, session_data_paths, session_store,
+    setup_dao_with_cookies, setup_dao_with_fetching, setup_dao_with_loading,
+};
 use crate::{
-    app::save_dao,
+    cli::OutputFormat,
     dao::{self, Dao},
-    domain::{page_type, url::Url},
-    dto::config::Config,
+    dto::{config::Config, Command, LoginStatus},
     error::UnwrapOrExit,
-    handler::{file_handler, terminal_handler},
+    handler::{
+        command_handler, file_handler, session_handler,
+        terminal_handler::{self, Credentials},
+    },
+};
+use itertools::Itertools;
+use std::{
+    env,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    process::Output,
 };
+use time::{Duration, OffsetDateTime};
+
+const PASSWORD_ENV: &str = "ATCODER_PASSWORD";
 
-pub fn run(config: &Config, check: bool) {
-    if check {
+pub fn run(
+    config: &Config,
+    check: bool,
+    format: OutputFormat,
+    username: Option<String>,
+    password_stdin: bool,
+    import: Option<PathBuf>,
+) {
+    if let Some(file) = import {
+        import_cookies(config, &file).unwrap_or_exit();
+    } else if check {
+        if let OutputFormat::Table = format {
+            print_session_lookup_order(config);
+            print_profiles(config);
+        }
         let dao = setup_dao_with_loading(config).unwrap_or_exit();
-        check_login(config, dao).unwrap_or_exit();
+        let login_status = check_login(config, dao).unwrap_or_exit();
+        print_login_status(&login_status, format).unwrap_or_exit();
     } else {
         let dao = setup_dao_with_fetching(config).unwrap_or_exit();
-        login(config, dao).unwrap_or_exit();
+        login(config, dao, username, password_stdin).unwrap_or_exit();
     }
 }
 
-fn login(config: &Config, dao: Dao) -> Result<(), Error> {
-    interactive_login(&dao, &config.app_config.url.login)?;
+fn login(
+    config: &Config,
+    dao: Dao,
+    username: Option<String>,
+    password_stdin: bool,
+) -> Result<(), Error> {
+    let url = &config.app_config.url;
+
+    match (username, read_password(config, password_stdin)?) {
+        (None, None) => interactive_login(&dao, config)?,
+        // Given credentials do not change on retry, so failure is final
+        (username, password) => {
+            let username = match username {
+                Some(username) => username,
+                None => terminal_handler::read_username().map_err(Error::Terminal)?,
+            };
+            let password = match password {
+                Some(password) => password,
+                None => terminal_handler::read_password().map_err(Error::Terminal)?,
+            };
+            dao.login(
+                Credentials { username, password },
+                &url.login,
+                &url.homepage,
+            )?;
+        }
+    }
 
     println!("Login Successful");
 
     save_dao(config, dao)?;
-    let session_data_file = &config.app_config.path.session_data;
-    println!("{} Created", session_data_file.display());
+    println!("{} Created", session_store(config).location());
 
     Ok(())
 }
 
-fn interactive_login(dao: &Dao, url: &Url<page_type::Login>) -> Result<(), Error> {
+/// Logs in with cookies of a browser session, for when password login is blocked
+fn import_cookies(config: &Config, file: &Path) -> Result<(), Error> {
+    let cookies = file_handler::load_cookie_export(file)?;
+    if cookies.is_empty() {
+        return Err(Error::NoCookies(file.to_path_buf()));
+    }
+
+    let dao = setup_dao_with_cookies(config, cookies)?;
+    if !dao.check_login(&config.app_config.url.homepage)? {
+        return Err(Error::NotLoggedIn(file.to_path_buf()));
+    }
+
+    let username = dao.username().unwrap_or_else(|| "unknown user".to_string());
+    println!("Import Successful: Logged in as {username}");
+
+    save_dao(config, dao)?;
+    println!("{} Created", session_store(config).location());
+
+    Ok(())
+}
+
+/// Logs in again in place of an expired session, if enabled and a terminal is attached
+pub(super) fn relogin(config: &Config) -> Result<Option<Dao>, Error> {
+    if !config.user_config.login.relogin || !io::stdin().is_terminal() {
+        return Ok(None);
+    }
+
+    eprintln!("Session has expired. Log in again");
+    let dao = setup_dao_with_fetching(config)?;
+    interactive_login(&dao, config)?;
+    save_dao(config, dao)?;
+
+    Ok(Some(setup_dao_with_loading(config)?))
+}
+
+fn interactive_login(dao: &Dao, config: &Config) -> Result<(), Error> {
+    let url = &config.app_config.url;
     let credentials = terminal_handler::read_credentials().map_err(Error::Terminal)?;
-    dao.login(credentials, url).or_else(|error| {
-        let should_retry = terminal_handler::ask_for_retry().map_err(Error::Terminal)?;
-        if should_retry {
-            interactive_login(dao, url)
+    dao.login(credentials, &url.login, &url.homepage)
+        .or_else(|error| {
+            let should_retry = terminal_handler::ask_for_retry().map_err(Error::Terminal)?;
+            if should_retry {
+                interactive_login(dao, config)
+            } else {
+                Err(Error::Dao(error))
+            }
+        })
+}
+
+/// Password given without prompting, from stdin, `ATCODER_PASSWORD` or `password_command` in this order
+///
+/// The explicit `--password-stdin` takes precedence over the environment.
+fn read_password(config: &Config, password_stdin: bool) -> Result<Option<String>, Error> {
+    if password_stdin {
+        let password = terminal_handler::read_stdin_line().map_err(Error::Terminal)?;
+        return Ok(Some(password));
+    }
+
+    if let Ok(password) = env::var(PASSWORD_ENV) {
+        return Ok(Some(password));
+    }
+
+    config
+        .user_config
+        .login
+        .password_command
+        .as_ref()
+        .map(run_password_command)
+        .transpose()
+}
+
+fn run_password_command(command: &Command) -> Result<String, Error> {
+    let output: Output = command_handler::run(command, None).map_err(Error::PasswordCommand)?;
+
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(Error::PasswordCommandFailed(message));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout.lines().next().unwrap_or_default().to_string())
+}
+
+fn print_session_lookup_order(config: &Config) {
+    let profile = profile(config);
+    let in_use = session_data_path(config, profile);
+
+    println!("Session lookup order:");
+    for (i, path) in session_data_paths(config).iter().enumerate() {
+        let path = path.profile_file(profile);
+        let status = if *path == in_use {
+            "in use"
+        } else if path.exists() {
+            "found"
         } else {
-            Err(Error::Dao(error))
+            "not found"
+        };
+        println!("  {}. {} ({status})", i + 1, path.display());
+    }
+    println!("Session: {}", session_store(config).location());
+}
+
+/// Lists profiles saved in any session location, marking the one in use
+fn print_profiles(config: &Config) {
+    let current = profile(config);
+    let profiles = session_data_paths(config)
+        .iter()
+        .flat_map(|path| path.profiles())
+        .chain([current.to_string()])
+        .sorted()
+        .dedup();
+
+    println!("Profiles:");
+    for profile in profiles {
+        let marker = if profile == current { "*" } else { " " };
+        match profile_session_store(config, &profile).load() {
+            Ok(session_data) => {
+                let username = session_data.username.as_deref().unwrap_or("unknown user");
+                let expires = session_data
+                    .expired_datetime()
+                    .map_or_else(|| "unknown".to_string(), |datetime| datetime.to_string());
+                println!("{marker} {profile}: {username} (expires: {expires})");
+            }
+            Err(error) => println!("{marker} {profile}: {error}"),
         }
-    })
+    }
 }
 
-fn check_login(config: &Config, dao: Dao) -> Result<bool, Error> {
+fn check_login(config: &Config, dao: Dao) -> Result<LoginStatus, Error> {
     let logged_in = dao.check_login(&config.app_config.url.homepage)?;
+    let session_data = dao.into_session_data();
 
     if logged_in {
-        println!("Logged in");
+        session_store(config).save(&session_data)?;
+    }
 
-        let session_data = dao.into_session_data();
-        file_handler::save(&config.app_config.path.session_data, &session_data);
-        println!("Expires: {:?}", session_data.expired_datetime());
-    } else {
-        println!("Not logged in");
+    let expires_at = session_data.expired_datetime().filter(|_| logged_in);
+    let warning_window = Duration::days(config.user_config.session.expiry_warning_days);
+    let expires_soon = expires_at
+        .is_some_and(|expires_at| expires_at - OffsetDateTime::now_utc() < warning_window);
+
+    Ok(LoginStatus {
+        profile: profile(config).to_string(),
+        session: session_store(config).location(),
+        logged_in,
+        username: session_data.username.filter(|_| logged_in),
+        expires_at,
+        expires_soon,
+    })
+}
+
+fn print_login_status(login_status: &LoginStatus, format: OutputFormat) -> Result<(), Error> {
+    if let Some(expires_at) = login_status
+        .expires_at
+        .filter(|_| login_status.expires_soon)
+    {
+        eprintln!("Warning: Session expires at {expires_at}. Run `login` again to renew it");
     }
 
-    Ok(logged_in)
+    match format {
+        OutputFormat::Table if login_status.logged_in => {
+            let username = login_status.username.as_deref().unwrap_or("unknown user");
+            println!("Logged in as {username}");
+            if let Some(expires_at) = login_status.expires_at {
+                println!("Expires: {expires_at}");
+            }
+        }
+        OutputFormat::Table => println!("Not logged in"),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(login_status)?),
+    }
+
+    Ok(())
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -66,6 +265,65 @@ pub enum Error {
     #[error("Terminal Input Error: {:?}", .0)]
     Terminal(#[source] std::io::Error),
 
+    #[error(transparent)]
+    SessionHandler(#[from] session_handler::Error),
+
     #[error(transparent)]
     FileHandler(#[from] file_handler::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    App(#[from] super::Error),
+
+    #[error("No atcoder.jp Cookies: {}", .0.display())]
+    NoCookies(PathBuf),
+
+    #[error("Imported Cookies Are Not Logged In: {}", .0.display())]
+    NotLoggedIn(PathBuf),
+
+    #[error("Failed to Run Password Command: {:?}", .0)]
+    PasswordCommand(#[source] std::io::Error),
+
+    #[error("Password Command Failed: {}", .0)]
+    PasswordCommandFailed(String),
+}
+
+#[cfg(test)]
+#[cfg(unix)]
+mod tests {
+    use super::*;
+
+    fn command(command: &str, args: &[&str]) -> Command {
+        Command {
+            command: command.to_string(),
+            args: args.iter().map(ToString::to_string).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_run_password_command() {
+        // Setup
+        let command = command("printf", &["secret\\nmetadata\\n"]);
+
+        // Run
+        let password = run_password_command(&command).unwrap();
+
+        // Verify
+        assert_eq!("secret", password);
+    }
+
+    #[test]
+    fn test_run_password_command_failed() {
+        // Setup
+        let command = command("false", &[]);
+
+        // Run
+        let result = run_password_command(&command);
+
+        // Verify
+        assert!(matches!(result, Err(Error::PasswordCommandFailed(_))));
+    }
 }
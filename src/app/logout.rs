@@ -0,0 +1,28 @@
+use super::{session_store, setup_dao_with_loading};
+use crate::{dao::Dao, dto::config::Config, error::UnwrapOrExit, handler::session_handler};
+
+pub fn run(config: &Config) {
+    let dao = setup_dao_with_loading(config).unwrap_or_exit();
+    logout(config, &dao).unwrap_or_exit();
+}
+
+fn logout(config: &Config, dao: &Dao) -> Result<(), Error> {
+    // The stored session is removed even if the server rejects it, e.g. when it has already expired
+    let url = &config.app_config.url;
+    match dao.logout(&url.logout, &url.homepage) {
+        Ok(()) => println!("Logout Successful"),
+        Err(error) => eprintln!("Warning: {error}"),
+    }
+
+    let store = session_store(config);
+    store.remove()?;
+    println!("{} Removed", store.location());
+
+    Ok(())
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    SessionHandler(#[from] session_handler::Error),
+}
@@ -1,96 +1,351 @@
 use crate::{
+    cli::{DiffStyle, ReportFormat},
+    domain::variables::{self, Variables},
     dto::{
-        config::{Config, LanguageConfig},
-        Command, Diff, TestCase, TestCaseFile,
+        config::{Config, LanguageConfig, UserConfig},
+        Command, CompileResult, CompileStatus, Diff, TaskInfo, TestCase, TestCaseFile,
+        TestCaseResult, TestReport, Verdict,
     },
     error::UnwrapOrExit,
-    handler::{command_handler, file_handler, terminal_handler},
+    handler::{
+        cache_handler::CompileCache,
+        command_handler::{self, Execution},
+        file_handler, terminal_handler,
+    },
+    utils::suggest,
 };
+use blake2::{Blake2s256, Digest};
+use itertools::Itertools;
 use std::{
-    path::Path,
-    process::{self, ExitStatus, Output},
+    env, fs,
+    path::{Path, PathBuf},
+    process::{self, Output},
+    time::Duration,
 };
 
-pub fn run(
-    config: &Config,
-    language: String,
-    task: String,
-    test_cases: Option<Vec<String>>,
-    verbose: bool,
-) {
-    let (language_config, test_dir) = setup(config, language).unwrap_or_exit();
-    let ac = test(language_config, test_dir, task, test_cases, verbose).unwrap_or_exit();
-    if ac {
-        println!("AC");
-    } else {
-        process::exit(1);
+pub struct Options {
+    pub test_cases: Option<Vec<String>>,
+    pub verbose: bool,
+    pub rebuild: bool,
+    pub time_limit: Duration,
+    pub format: ReportFormat,
+    pub diff_style: DiffStyle,
+}
+
+pub fn run(config: &Config, language: Option<String>, task: String, options: Options) {
+    let (language_config, test_dir) = setup(config, language, &task).unwrap_or_exit();
+    let compile_cache = CompileCache::new(&config.app_config.path.compile_hash);
+    if options.rebuild {
+        compile_cache.clear().unwrap_or_exit();
+    }
+
+    let report = test(&language_config, test_dir, task, &options, &compile_cache).unwrap_or_exit();
+    print_report(&report, &options).unwrap_or_exit();
+
+    if report.verdict != Verdict::AC {
+        process::exit(report.verdict.exit_code());
     }
 }
 
-fn setup(config: &Config, language: String) -> Result<(&LanguageConfig, &Path), Error> {
-    let language_config = config
-        .user_config
-        .language_config(&language)
-        .ok_or(Error::Config(language))?;
+fn setup<'a>(
+    config: &'a Config,
+    language: Option<String>,
+    task: &str,
+) -> Result<(LanguageConfig, &'a Path), Error> {
+    let user_config = &config.user_config;
     let test_dir = &config.app_config.path.test;
+    let variables = variables(config, task, test_dir);
+
+    let language_config = match language {
+        Some(language) => find_language(user_config, language)?.expand(variables)?,
+        None => {
+            let (language_config, reason) = choose_language(user_config, variables)?;
+            eprintln!("Language: {} ({reason})", language_config.name);
+            language_config
+        }
+    };
 
     Ok((language_config, test_dir))
 }
 
+fn find_language(user_config: &UserConfig, language: String) -> Result<&LanguageConfig, Error> {
+    user_config.language_config(&language).ok_or_else(|| {
+        let names = user_config
+            .languages()
+            .iter()
+            .map(|config| config.name.as_str());
+        let suggestion = suggest::closest(&language, names)
+            .map(|name| format!(" Did you mean `{name}`?"))
+            .unwrap_or_default();
+        Error::Config(language, suggestion)
+    })
+}
+
+/// Language whose source for the task was modified most recently, otherwise `default_language`
+///
+/// Among languages sharing the source, e.g. `python` and `pypy`, `default_language` or the first one is chosen.
+fn choose_language(
+    user_config: &UserConfig,
+    variables: Variables,
+) -> Result<(LanguageConfig, String), Error> {
+    let default_language = user_config.default_language.as_deref();
+
+    // Languages whose variables are unavailable for the task are not candidates
+    let sources = user_config
+        .languages()
+        .iter()
+        .filter_map(|config| config.expand(variables.clone()).ok())
+        .filter_map(|config| Some((file_handler::modified(&config.src_path)?, config)))
+        .collect_vec();
+    let latest = sources.iter().map(|(modified, _)| *modified).max();
+    let mut candidates = sources
+        .into_iter()
+        .filter(|(modified, _)| Some(*modified) == latest)
+        .map(|(_, config)| config)
+        .collect_vec();
+
+    if !candidates.is_empty() {
+        let i = candidates
+            .iter()
+            .position(|config| Some(config.name.as_str()) == default_language)
+            .unwrap_or_default();
+        let language_config = candidates.swap_remove(i);
+        let reason = format!(
+            "{} was modified most recently",
+            language_config.src_path.display()
+        );
+        return Ok((language_config, reason));
+    }
+
+    let language = default_language.ok_or(Error::NoLanguage)?;
+    let language_config = find_language(user_config, language.to_string())?;
+    Ok((
+        language_config.expand(variables)?,
+        "default_language".to_string(),
+    ))
+}
+
+fn variables(config: &Config, task: &str, test_dir: &Path) -> Variables {
+    let variables = Variables::new(env::vars())
+        .with("task", task)
+        .with("test_dir", test_dir.join(task).to_string_lossy());
+
+    // The contest is known only for the tasks fetched last
+    let tasks_info =
+        file_handler::load::<Vec<TaskInfo>>(&config.app_config.path.tasks_info).unwrap_or_default();
+    match tasks_info.iter().find(|task_info| task_info.task == task) {
+        Some(task_info) => variables.with("contest", task_info.contest_url.contest()),
+        None => variables,
+    }
+}
+
 fn test(
     language_config: &LanguageConfig,
     test_dir: &Path,
     task: String,
-    test_cases: Option<Vec<String>>,
-    verbose: bool,
-) -> Result<bool, Error> {
-    if !compile(&language_config.compile)? {
-        return Ok(false);
+    options: &Options,
+    compile_cache: &CompileCache,
+) -> Result<TestReport, Error> {
+    let compile = compile(language_config, compile_cache)?;
+
+    let (verdict, test_cases) = if compile.status == CompileStatus::Failed {
+        (Verdict::CE, Vec::new())
+    } else {
+        let test_case_files =
+            file_handler::load_test_cases(test_dir, &task, options.test_cases.clone())?;
+        let test_cases = verify(
+            &language_config.execute,
+            test_case_files,
+            options.time_limit,
+        )?;
+        let verdict = test_cases
+            .iter()
+            .map(|test_case| test_case.verdict)
+            .max()
+            .unwrap_or(Verdict::AC);
+        (verdict, test_cases)
+    };
+
+    Ok(TestReport {
+        task,
+        language: language_config.name.clone(),
+        verdict,
+        compile,
+        test_cases,
+    })
+}
+
+fn print_report(report: &TestReport, options: &Options) -> Result<(), Error> {
+    match options.format {
+        ReportFormat::Text => {
+            let TestReport {
+                verdict,
+                compile,
+                test_cases,
+                ..
+            } = report;
+
+            if compile.status == CompileStatus::Failed {
+                terminal_handler::print_compile_error(&compile.output, options.verbose);
+            } else if options.verbose {
+                // Warnings of the compiler
+                eprint!("{}", compile.output);
+            }
+
+            let diffs = test_cases
+                .iter()
+                .filter_map(|test_case| test_case.diff.as_ref());
+            terminal_handler::print_diffs(diffs, options.verbose, options.diff_style);
+
+            println!("{verdict}");
+        }
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(report)?),
+        ReportFormat::Junit => println!("{}", terminal_handler::make_junit(report)),
     }
 
-    let test_case_files = file_handler::load_test_cases(test_dir, &task, test_cases)?;
+    Ok(())
+}
+
+fn compile(
+    language: &LanguageConfig,
+    compile_cache: &CompileCache,
+) -> Result<CompileResult, Error> {
+    let LanguageConfig {
+        src_path,
+        compile,
+        version,
+        compile_inputs,
+        ..
+    } = language;
+    let skipped = |status| CompileResult {
+        status,
+        output: String::new(),
+    };
+    let Some(command) = compile else {
+        return Ok(skipped(CompileStatus::NotRequired));
+    };
 
-    let diffs = verify(&language_config.execute, test_case_files)?;
+    let inputs = compile_inputs
+        .iter()
+        .map(|pattern| file_handler::glob(pattern))
+        .flatten_ok()
+        .collect::<Result<Vec<_>, _>>()?;
+    // Without readable sources, the program is compiled every time
+    let hash = compile_hash(src_path, &inputs, command, version.as_ref());
+    // Nor is it skipped unless the compiled program is known to exist
+    let program_exists = language
+        .compiled_program()
+        .is_some_and(|program| program.exists());
+    if let Some(hash) = hash
+        .as_deref()
+        .filter(|hash| program_exists && compile_cache.is_fresh(hash))
+    {
+        eprintln!("Compile Skipped: Unchanged ({})", &hash[..8]);
+        return Ok(skipped(CompileStatus::Cached));
+    }
 
-    terminal_handler::print_diffs(&diffs, verbose)?;
+    let Output {
+        status,
+        stdout,
+        stderr,
+    } = command_handler::run::<Output>(command, None)?;
+    let success = status.success();
+    match hash {
+        Some(hash) if success => compile_cache.save(&hash)?,
+        _ => compile_cache.clear()?,
+    }
 
-    Ok(diffs.is_empty())
+    // Most compilers write diagnostics to stderr, but some to stdout
+    let output = String::from_utf8_lossy(&stderr) + String::from_utf8_lossy(&stdout);
+    Ok(CompileResult {
+        status: if success {
+            CompileStatus::Succeeded
+        } else {
+            CompileStatus::Failed
+        },
+        output: output.into_owned(),
+    })
 }
 
-fn compile(command: &Option<Command>) -> Result<bool, Error> {
-    let result = if let Some(command) = command {
-        let exit_status = command_handler::run::<ExitStatus>(command, None)?;
-        exit_status.success()
-    } else {
-        true
+/// Hash of the sources, the other input files, the compile command and the output of the version command if any
+///
+/// The hash is unknown if the version command fails, so that the program is compiled.
+fn compile_hash(
+    src_path: &Path,
+    inputs: &[PathBuf],
+    command: &Command,
+    version: Option<&Command>,
+) -> Option<String> {
+    let mut hasher = Blake2s256::new();
+    let mut update = |bytes: &[u8]| {
+        hasher.update((bytes.len() as u64).to_le_bytes());
+        hasher.update(bytes);
     };
 
-    Ok(result)
+    for (file, contents) in file_handler::load_sources(src_path).ok()? {
+        update(file.to_string_lossy().as_bytes());
+        update(&contents);
+    }
+    for file in inputs {
+        update(file.to_string_lossy().as_bytes());
+        update(&fs::read(file).ok()?);
+    }
+    update(&serde_json::to_vec(command).ok()?);
+    if let Some(version) = version {
+        update(&command_handler::run::<Output>(version, None).ok()?.stdout);
+    }
+
+    Some(format!("{:x}", hasher.finalize()))
 }
 
-fn verify(command: &Command, test_case_files: Vec<TestCaseFile>) -> Result<Vec<Diff>, Error> {
-    let mut diffs = Vec::new();
+/// Results of the test cases, stopping at the first RE
+fn verify(
+    command: &Command,
+    test_case_files: Vec<TestCaseFile>,
+    time_limit: Duration,
+) -> Result<Vec<TestCaseResult>, Error> {
+    let mut results = Vec::new();
 
     for test_case_file in test_case_files {
-        let status = verify_one(command, test_case_file)?;
-        match status {
-            Status::RE(diff) => {
-                diffs.push(diff);
-                return Ok(diffs);
-            }
-            Status::WA(diff) => {
-                diffs.push(diff);
-            }
-            Status::AC => (),
+        let result = verify_one(command, test_case_file, time_limit)?;
+        let verdict = result.verdict;
+        results.push(result);
+
+        if verdict == Verdict::RE {
+            break;
         }
     }
 
-    Ok(diffs)
+    Ok(results)
 }
 
-fn verify_one(command: &Command, test_case_file: TestCaseFile) -> Result<Status, Error> {
-    let output = command_handler::run(command, Some(&test_case_file.test_case.input))?;
-    judge(output, test_case_file)
+fn verify_one(
+    command: &Command,
+    test_case_file: TestCaseFile,
+    time_limit: Duration,
+) -> Result<TestCaseResult, Error> {
+    let file = test_case_file.file.clone();
+    let Execution {
+        output,
+        elapsed,
+        memory_kib,
+        timed_out,
+    } = command_handler::run_with_time_limit(command, &test_case_file.test_case.input, time_limit)?;
+
+    let (verdict, diff) = match judge(output, timed_out, test_case_file)? {
+        Status::AC => (Verdict::AC, None),
+        Status::WA(diff) => (Verdict::WA, Some(diff)),
+        Status::TLE(diff) => (Verdict::TLE, Some(diff)),
+        Status::RE(diff) => (Verdict::RE, Some(diff)),
+    };
+
+    Ok(TestCaseResult {
+        file,
+        verdict,
+        time_ms: elapsed.as_millis() as u64,
+        memory_kib,
+        diff,
+    })
 }
 
 fn judge(
@@ -99,6 +354,7 @@ fn judge(
         stdout,
         stderr,
     }: Output,
+    timed_out: bool,
     TestCaseFile {
         test_case: TestCase {
             input,
@@ -110,19 +366,20 @@ fn judge(
     let stdout = String::from_utf8(stdout)?;
     let stderr = String::from_utf8(stderr)?;
 
-    let status = if expected.split_whitespace().eq(stdout.split_whitespace()) {
+    let status = if !timed_out && expected.split_whitespace().eq(stdout.split_whitespace()) {
         Status::AC
     } else {
-        let actual = format!("{stdout}\n\n{stderr}");
-
         let diff = Diff {
             input,
             expected,
-            actual,
+            actual: stdout,
+            stderr,
             file,
         };
 
-        if status.success() {
+        if timed_out {
+            Status::TLE(diff)
+        } else if status.success() {
             Status::WA(diff)
         } else {
             Status::RE(diff)
@@ -132,16 +389,27 @@ fn judge(
     Ok(status)
 }
 
+#[allow(clippy::upper_case_acronyms)]
 enum Status {
     AC,
     WA(Diff),
+    TLE(Diff),
     RE(Diff),
 }
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
-    #[error("Config of {0} Not Found in Config.toml")]
-    Config(String),
+    #[error("Config of {0} Not Found in Config.toml.{1}")]
+    Config(String, String),
+
+    #[error("Language Not Specified: none of `src_path` for the task exists and `default_language` is not set")]
+    NoLanguage,
+
+    #[error(transparent)]
+    Variables(#[from] variables::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
 
     #[error(transparent)]
     IO(#[from] std::io::Error),
@@ -151,16 +419,159 @@ pub enum Error {
 
     #[error(transparent)]
     FileHandler(#[from] file_handler::Error),
-
-    #[error(transparent)]
-    TerminalHanlder(#[from] terminal_handler::Error),
 }
 
 #[cfg(test)]
 mod tests {
+    #[cfg(unix)]
+    use std::os::unix::process::ExitStatusExt;
+    #[cfg(windows)]
     use std::os::windows::process::ExitStatusExt;
 
     use super::*;
+    use crate::utils::test::temp_dir;
+    use std::{
+        fs::{self, File},
+        process::ExitStatus,
+        time::SystemTime,
+    };
+
+    #[test]
+    fn test_compile_hash() {
+        // Setup
+        let dir = temp_dir("compile_hash");
+        let src_path = dir.join("main.cpp");
+        let command = |args: &str| Command {
+            command: "echo".to_string(),
+            args: vec![args.to_string()],
+            ..Default::default()
+        };
+
+        // Run
+        fs::write(&src_path, "int main() {}").unwrap();
+        let original = compile_hash(&src_path, &[], &command("-O2"), None);
+        let same = compile_hash(&src_path, &[], &command("-O2"), None);
+        let flags_changed = compile_hash(&src_path, &[], &command("-O0"), None);
+        let version = compile_hash(&src_path, &[], &command("-O2"), Some(&command("1.0")));
+        let version_changed = compile_hash(&src_path, &[], &command("-O2"), Some(&command("2.0")));
+        fs::write(&src_path, "int main() { return 0; }").unwrap();
+        let source_changed = compile_hash(&src_path, &[], &command("-O2"), None);
+        let inputs = [dir.join("lib.hpp")];
+        fs::write(dir.join("lib.hpp"), "int f();").unwrap();
+        let with_header = compile_hash(&src_path, &inputs, &command("-O2"), None);
+        fs::write(dir.join("lib.hpp"), "int g();").unwrap();
+        let header_changed = compile_hash(&src_path, &inputs, &command("-O2"), None);
+        let no_source = compile_hash(&dir.join("none.cpp"), &[], &command("-O2"), None);
+
+        // Verify
+        assert!(original.is_some());
+        assert_eq!(original, same);
+        assert_ne!(original, flags_changed);
+        assert_ne!(original, version);
+        assert_ne!(version, version_changed);
+        assert_ne!(original, source_changed);
+        assert_ne!(source_changed, with_header);
+        assert_ne!(with_header, header_changed);
+        assert!(no_source.is_none());
+    }
+
+    #[test]
+    fn test_compiled_program() {
+        // Setup
+        let user_config: UserConfig = toml::from_str(
+            r#"
+            [[language]]
+            name = "cpp"
+            id = "5001"
+            src_path = "main.cpp"
+            compile = "g++ -o a.out main.cpp"
+            execute = { command = "./a.out", working_dir = "build" }
+
+            [[language]]
+            name = "java"
+            id = "5005"
+            src_path = "Main.java"
+            compile = "javac Main.java"
+            execute = "java Main"
+
+            [[language]]
+            name = "java-class"
+            id = "5005"
+            src_path = "Main.java"
+            compile = "javac Main.java"
+            execute = "java Main"
+            compile_output = "Main.class"
+            "#,
+        )
+        .unwrap();
+
+        // Run
+        let programs = user_config
+            .languages()
+            .iter()
+            .map(LanguageConfig::compiled_program)
+            .collect_vec();
+
+        // Verify
+        assert_eq!(
+            vec![
+                Some(PathBuf::from("build/./a.out")),
+                None,
+                Some(PathBuf::from("Main.class"))
+            ],
+            programs
+        );
+    }
+
+    #[test]
+    fn test_choose_language() {
+        // Setup
+        let dir = temp_dir("choose_language").display().to_string();
+        let user_config: UserConfig = toml::from_str(&format!(
+            r#"
+            default_language = "pypy"
+
+            [[language]]
+            name = "rust"
+            id = "5054"
+            src_path = '{dir}/{{task}}.rs'
+            execute = "cargo run"
+
+            [[language]]
+            name = "python"
+            id = "5055"
+            src_path = '{dir}/{{task}}.py'
+            execute = "python3 {{src}}"
+
+            [[language]]
+            name = "pypy"
+            id = "5078"
+            src_path = '{dir}/{{task}}.py'
+            execute = "pypy3 {{src}}"
+            "#,
+        ))
+        .unwrap();
+        let now = SystemTime::now();
+        for (file, modified) in [("A.rs", now), ("A.py", now - Duration::from_secs(60))] {
+            let file = File::create(format!("{dir}/{file}")).unwrap();
+            file.set_modified(modified).unwrap();
+        }
+        File::create(format!("{dir}/B.py")).unwrap();
+        let variables = |task| Variables::new([]).with("task", task);
+
+        // Run
+        let a = choose_language(&user_config, variables("A")).unwrap();
+        let b = choose_language(&user_config, variables("B")).unwrap();
+        let c = choose_language(&user_config, variables("C")).unwrap();
+
+        // Verify
+        assert_eq!("rust", a.0.name);
+        assert_eq!(format!("{dir}/A.rs was modified most recently"), a.1);
+        assert_eq!("pypy", b.0.name);
+        assert_eq!(vec![format!("{dir}/B.py")], b.0.execute.args);
+        assert_eq!("pypy", c.0.name);
+        assert_eq!("default_language", c.1);
+    }
 
     #[test]
     fn test_judge_ac() {
@@ -184,7 +595,7 @@ mod tests {
         };
 
         // Verify
-        match judge(output, test_case_file) {
+        match judge(output, false, test_case_file) {
             Ok(Status::AC) => (),
             _ => unreachable!(),
         }
@@ -212,16 +623,18 @@ mod tests {
         };
 
         // Verify
-        match judge(output, test_case_file) {
+        match judge(output, false, test_case_file) {
             Ok(Status::WA(Diff {
                 input,
                 expected,
                 actual,
+                stderr,
                 file,
             })) => {
                 assert_eq!(input, "input");
                 assert_eq!(expected, "expected");
-                assert_eq!(actual, "e\n\n");
+                assert_eq!(actual, "e");
+                assert_eq!(stderr, "");
                 assert_eq!(file, "test.txt");
             }
             _ => unreachable!(),
@@ -251,16 +664,18 @@ mod tests {
         };
 
         // Verify
-        match judge(output, test_case_file) {
+        match judge(output, false, test_case_file) {
             Ok(Status::RE(Diff {
                 input,
                 expected,
                 actual,
+                stderr,
                 file,
             })) => {
                 assert_eq!(input, "input");
                 assert_eq!(expected, "expected");
-                assert_eq!(actual, "e\n\nerror");
+                assert_eq!(actual, "e");
+                assert_eq!(stderr, "error");
                 assert_eq!(file, "test.txt");
             }
             _ => unreachable!(),
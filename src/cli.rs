@@ -1,10 +1,27 @@
-use crate::domain::url::FetchTaskUrl;
-use clap::{Parser, Subcommand};
+use crate::domain::{path, url::FetchTaskUrl};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::{path::PathBuf, time::Duration};
 
 #[derive(Parser)]
 pub struct Cli {
     #[clap(subcommand)]
     pub command: Command,
+
+    /// Serve pages from the local cache without accessing the network
+    #[arg(long, global = true)]
+    pub offline: bool,
+
+    /// Profile whose session is used, e.g. an alt account for virtual participation
+    #[arg(long, global = true, value_parser = parse_profile)]
+    pub profile: Option<String>,
+
+    /// Record HTTP exchanges into a fixture file for tests
+    #[arg(long, global = true, hide = true)]
+    pub record: Option<PathBuf>,
+
+    /// Replay HTTP exchanges from a fixture file instead of accessing the network
+    #[arg(long, global = true, hide = true, conflicts_with = "record")]
+    pub replay: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
@@ -14,6 +31,44 @@ pub enum Command {
         /// Check login status
         #[arg(short, long)]
         check: bool,
+
+        /// Output format of the login status
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table, requires = "check")]
+        format: OutputFormat,
+
+        /// Username, prompted if not given
+        #[arg(short, long, env = "ATCODER_USERNAME")]
+        username: Option<String>,
+
+        /// Read the password from stdin instead of prompting
+        ///
+        /// Without this flag, the password is read from `ATCODER_PASSWORD` or `password_command` in the config.
+        #[arg(long)]
+        password_stdin: bool,
+
+        /// Import the session from cookies exported from a browser, as Netscape cookies.txt or JSON
+        ///
+        /// A relative path is resolved from the current directory. The username, which may come from
+        /// `ATCODER_USERNAME`, is ignored, since the session tells it.
+        #[arg(
+            long,
+            value_name = "FILE",
+            value_parser = parse_absolute_path,
+            conflicts_with_all = ["check", "password_stdin"]
+        )]
+        import: Option<PathBuf>,
+    },
+
+    /// Create `.atcoder` with a config in the current directory
+    Init,
+
+    /// Logout and remove the stored session
+    Logout,
+
+    /// Validate or show the user config
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
     },
 
     /// Fetch test suite
@@ -28,12 +83,26 @@ pub enum Command {
         url: FetchTaskUrl,
     },
 
+    /// Show contest information
+    Contest {
+        /// Contest ID (e.g. abc388)
+        contest: String,
+
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+
     /// Test
+    ///
+    /// Exits with 0 for AC, 2 for WA, 3 for RE, 4 for TLE, 5 for CE and 1 for other errors.
     #[command(visible_alias = "t")]
     Test {
-        language: String,
-
-        task: String,
+        /// Language followed by the task, e.g. "rust A", or only the task
+        ///
+        /// Without the language, the one whose `src_path` for the task was modified most recently is used,
+        /// or `default_language` in the config.
+        #[arg(required = true, num_args = 1..=2, value_names = ["LANGUAGE", "TASK"])]
+        language_and_task: Vec<String>,
 
         /// e.g. "--test-cases 1 3" specifies that test cases 1 and 3 will be used, and test case 2 will be skipped.
         /// If not specified, all test cases will be used.
@@ -42,9 +111,84 @@ pub enum Command {
 
         #[arg(long, short)]
         verbose: bool,
+
+        /// Compile even if the sources, the compile command and the compiler are unchanged
+        #[arg(long)]
+        rebuild: bool,
+
+        /// Time limit for each test case, after which the program is killed as TLE
+        #[arg(long, value_name = "SECONDS", default_value = "2", value_parser = parse_seconds)]
+        time_limit: Duration,
+
+        /// Output format of the results
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+
+        /// How the expected and actual output of failed test cases are shown in the text format
+        #[arg(long = "diff", value_name = "STYLE", value_enum, default_value_t = DiffStyle::Blocks)]
+        diff_style: DiffStyle,
     },
 }
 
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Check the config files for unknown keys, missing commands and paths
+    Check,
+
+    /// Show the config resolved from the config files and environment variables
+    Show,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+/// Splits `[LANGUAGE] TASK` given to `test`
+pub fn split_language_and_task(mut language_and_task: Vec<String>) -> (Option<String>, String) {
+    let task = language_and_task.pop().unwrap();
+    (language_and_task.pop(), task)
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ReportFormat {
+    /// Diffs of the failed test cases and the verdict
+    Text,
+    Json,
+    /// JUnit XML for CI
+    Junit,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum DiffStyle {
+    /// Input, expected and actual output one after another
+    Blocks,
+    /// Lines of the expected and actual output interleaved with `-` and `+`
+    Unified,
+    /// Expected and actual output in two columns
+    SideBySide,
+}
+
+fn parse_profile(s: &str) -> Result<String, String> {
+    path::is_valid_profile(s)
+        .then(|| s.to_string())
+        .ok_or_else(|| format!("invalid profile: {s}, which must not contain `/`, `\\` or `.`"))
+}
+
+/// Resolved on parsing, before moving to the project root
+fn parse_absolute_path(s: &str) -> Result<PathBuf, String> {
+    std::path::absolute(s).map_err(|error| format!("invalid path: {s}: {error}"))
+}
+
+fn parse_seconds(s: &str) -> Result<Duration, String> {
+    s.parse::<f64>()
+        .ok()
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .filter(|duration| !duration.is_zero())
+        .ok_or_else(|| format!("invalid seconds: {s}"))
+}
+
 fn append_txt_extension(s: &str) -> Result<String, String> {
     let file = if s.ends_with(".txt") {
         s.to_string()
@@ -1,23 +1,33 @@
 use crate::{
-    domain::{page_type, url::Url},
-    dto::{SessionData, TestSuite},
+    domain::{html::Html, page_type, url::Url},
+    dto::{ContestInfo, SessionData, TestSuite},
     handler::{
         http_handler::{self, HttpHandler},
         terminal_handler::Credentials,
     },
 };
 use dto::LoginData;
+use std::cell::RefCell;
 
 pub struct Dao {
     http_handler: HttpHandler,
-    csrf_token: String,
+    csrf_token: RefCell<String>,
+    username: RefCell<Option<String>>,
 }
 
 impl Dao {
     pub fn new(http_handler: HttpHandler, csrf_token: String) -> Self {
         Self {
             http_handler,
-            csrf_token,
+            csrf_token: RefCell::new(csrf_token),
+            username: RefCell::new(None),
+        }
+    }
+
+    pub fn with_username(self, username: Option<String>) -> Self {
+        Self {
+            username: RefCell::new(username),
+            ..self
         }
     }
 
@@ -33,48 +43,182 @@ impl Dao {
         &self,
         Credentials { username, password }: Credentials,
         url: &Url<page_type::Login>,
+        homepage: &Url<page_type::Home>,
     ) -> Result<(), Error> {
         let login_data = LoginData {
             username: &username,
             password: &password,
-            csrf_token: &self.csrf_token,
         };
 
-        let html = self
-            .http_handler
-            .post::<_, page_type::Home>(url, login_data)?;
+        let html = self.post_form::<_, page_type::Home>(url, login_data.into(), homepage)?;
 
         match html.title() {
-            Some(title) if title == "AtCoder" => Ok(()),
+            Some(title) if title == "AtCoder" => {
+                self.username.replace(Some(username));
+                Ok(())
+            }
             Some(_) => Err(Error::LoginFailed),
             None => Err(Error::Others("<title> Not Found".to_string())),
         }
     }
 
+    /// Invalidates the session on the server
+    pub fn logout(
+        &self,
+        url: &Url<page_type::Logout>,
+        homepage: &Url<page_type::Home>,
+    ) -> Result<(), Error> {
+        let html = self.post_form::<_, page_type::Home>(url, Vec::new(), homepage)?;
+
+        if html.is_logged_out() {
+            Ok(())
+        } else {
+            Err(Error::LogoutFailed)
+        }
+    }
+
     pub fn fetch_test_suite(&self, url: &Url<page_type::Task>) -> Result<TestSuite, Error> {
-        let html = self.http_handler.get(url)?;
-        Ok(html.test_suite())
+        let html = self.get_cached(url)?;
+        let test_suite = html.test_suite();
+
+        if test_suite.is_empty() && html.is_logged_out() {
+            return Err(Error::NotLoggedIn);
+        }
+
+        Ok(test_suite)
     }
 
     pub fn fetch_task_screen_names(
         &self,
         tasks_url: &Url<page_type::Tasks>,
     ) -> Result<Vec<String>, Error> {
-        let html = self.http_handler.get(tasks_url)?;
-        Ok(html.task_screen_names())
+        let html = self.get_cached(tasks_url)?;
+        let task_screen_names = html.task_screen_names();
+
+        if task_screen_names.is_empty() && html.is_logged_out() {
+            return Err(Error::NotLoggedIn);
+        }
+
+        Ok(task_screen_names)
+    }
+
+    pub fn fetch_contest_info(
+        &self,
+        contest_url: &Url<page_type::ContestHome>,
+    ) -> Result<ContestInfo, Error> {
+        let html = self.get_cached(contest_url)?;
+
+        let title = html
+            .contest_title()
+            .ok_or_else(|| Error::Others("Contest Title Not Found".to_string()))?;
+        let (start_time, end_time) = html
+            .contest_duration()
+            .ok_or_else(|| Error::Others("Contest Duration Not Found".to_string()))?;
+        let task_scores = html.task_scores();
+
+        let mut tasks = self.get_cached(&contest_url.tasks_url())?.tasks();
+        for task in &mut tasks {
+            task.score = task_scores.get(&task.label).copied();
+        }
+
+        Ok(ContestInfo {
+            title,
+            start_time,
+            end_time,
+            duration_minutes: (end_time - start_time).whole_minutes(),
+            rated_range: html.rated_range(),
+            penalty: html.penalty(),
+            tasks,
+        })
     }
 
     pub fn into_session_data(self) -> SessionData {
         SessionData {
             cookies: self.http_handler.into_cookies(),
-            csrf_token: self.csrf_token,
+            csrf_token: self.csrf_token.into_inner(),
+            username: self.username.into_inner(),
         }
     }
 
+    pub fn username(&self) -> Option<String> {
+        self.username.borrow().clone()
+    }
+
+    /// Also updates the username when it is found on the page
     pub fn check_login(&self, url: &Url<page_type::Home>) -> Result<bool, Error> {
+        let html = self.get(url)?;
+
+        match html.username() {
+            Some(username) => {
+                self.username.replace(Some(username));
+                Ok(true)
+            }
+            None => Ok(!html.has_sign_up_button()),
+        }
+    }
+}
+
+impl Dao {
+    fn get<PageType>(&self, url: &Url<PageType>) -> Result<Html<PageType>, Error> {
         let html = self.http_handler.get(url)?;
-        Ok(!html.has_sign_up_button())
+        self.update_csrf_token(&html);
+        Ok(html)
+    }
+
+    fn get_cached<PageType>(&self, url: &Url<PageType>) -> Result<Html<PageType>, Error> {
+        let html = self.http_handler.get_cached(url)?;
+        self.update_csrf_token(&html);
+        Ok(html)
     }
+
+    /// POSTs a form with the CSRF token, and if the token is rejected, refreshes it from the homepage and retries once
+    ///
+    /// The homepage always has the token, unlike the URL POSTed to, e.g. `/logout`.
+    fn post_form<RequestPageType, ResponsePageType>(
+        &self,
+        url: &Url<RequestPageType>,
+        form: Vec<(&'static str, &str)>,
+        homepage: &Url<page_type::Home>,
+    ) -> Result<Html<ResponsePageType>, Error> {
+        let csrf_token = self.csrf_token.borrow().clone();
+
+        let html = match self.post_with_csrf_token(url, &form, &csrf_token) {
+            Err(error) if is_csrf_rejection(&error) => {
+                let csrf_token = Self::fetch_csrf_token(&self.http_handler, homepage)?;
+                self.csrf_token.replace(csrf_token.clone());
+                self.post_with_csrf_token(url, &form, &csrf_token)?
+            }
+            result => result?,
+        };
+
+        self.update_csrf_token(&html);
+        Ok(html)
+    }
+
+    fn post_with_csrf_token<RequestPageType, ResponsePageType>(
+        &self,
+        url: &Url<RequestPageType>,
+        form: &[(&'static str, &str)],
+        csrf_token: &str,
+    ) -> Result<Html<ResponsePageType>, http_handler::Error> {
+        let form = form
+            .iter()
+            .copied()
+            .chain([("csrf_token", csrf_token)])
+            .collect::<Vec<_>>();
+        self.http_handler.post(url, form)
+    }
+
+    fn update_csrf_token<PageType>(&self, html: &Html<PageType>) {
+        if let Some(csrf_token) = html.csrf_token() {
+            self.csrf_token.replace(csrf_token);
+        }
+    }
+}
+
+/// AtCoder runs on Revel, whose CSRF filter answers 403 Forbidden to a stale token
+fn is_csrf_rejection(error: &http_handler::Error) -> bool {
+    matches!(error, http_handler::Error::Status { code: 403, .. })
 }
 
 pub mod dto {
@@ -84,22 +228,11 @@ pub mod dto {
     pub(super) struct LoginData<'a> {
         pub username: &'a str,
         pub password: &'a str,
-        pub csrf_token: &'a str,
     }
 
     impl<'a> From<LoginData<'a>> for Vec<(&str, &'a str)> {
-        fn from(
-            LoginData {
-                username,
-                password,
-                csrf_token,
-            }: LoginData<'a>,
-        ) -> Self {
-            vec![
-                ("username", username),
-                ("password", password),
-                ("csrf_token", csrf_token),
-            ]
+        fn from(LoginData { username, password }: LoginData<'a>) -> Self {
+            vec![("username", username), ("password", password)]
         }
     }
 }
@@ -115,6 +248,12 @@ pub enum Error {
     #[error("Login Failed")]
     LoginFailed,
 
+    #[error("Logout Failed")]
+    LogoutFailed,
+
+    #[error("Not Logged In: the session has expired. Run `login` again")]
+    NotLoggedIn,
+
     #[error("{}", .0)]
     Others(String),
 }
@@ -122,84 +261,286 @@ pub enum Error {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ureq::Agent;
+    use crate::utils::test::FixtureTransport;
+
+    const HOMEPAGE_URL: &str = "https://atcoder.jp/home";
+    const LOGIN_URL: &str = "https://atcoder.jp/login";
+
+    #[test]
+    fn test_fetch_csrf_token() {
+        // Setup
+        let http_handler = FixtureTransport::new()
+            .route_get(HOMEPAGE_URL, "homepage.html")
+            .into_http_handler();
+
+        // Run
+        let csrf_token = Dao::fetch_csrf_token(&http_handler, &HOMEPAGE_URL.to_string().into());
+
+        // Verify
+        assert_eq!("fixture-csrf-token", csrf_token.unwrap());
+    }
 
     #[test]
-    #[ignore]
     fn test_login() {
         // Setup
-        let http_handler = HttpHandler::new(Agent::new());
-        let url = "https://atcoder.jp/login".to_string().into();
-        let csrf_token = Dao::fetch_csrf_token(&http_handler, &url).unwrap();
-        let dao = Dao::new(http_handler, csrf_token);
+        let http_handler = FixtureTransport::new()
+            .route_post(LOGIN_URL, "homepage_logged_in.html")
+            .into_http_handler();
+        let dao = Dao::new(http_handler, "fixture-csrf-token".to_string());
+        let credentials = Credentials {
+            username: "fixture_user".to_string(),
+            password: "password".to_string(),
+        };
 
-        let username = rprompt::prompt_reply("username:").unwrap();
-        let password = rpassword::prompt_password("password:").unwrap();
+        // Run
+        let result = dao.login(
+            credentials,
+            &LOGIN_URL.to_string().into(),
+            &HOMEPAGE_URL.to_string().into(),
+        );
+
+        // Verify
+        assert!(result.is_ok())
+    }
+
+    #[test]
+    fn test_login_with_stale_csrf_token() {
+        // Setup
+        let http_handler = FixtureTransport::new()
+            .route_get(HOMEPAGE_URL, "homepage.html")
+            .route_post(LOGIN_URL, "homepage_logged_in.html")
+            .reject_csrf_token("stale-csrf-token")
+            .into_http_handler();
+        let dao = Dao::new(http_handler, "stale-csrf-token".to_string());
+        let credentials = Credentials {
+            username: "fixture_user".to_string(),
+            password: "password".to_string(),
+        };
+
+        // Run
+        let result = dao.login(
+            credentials,
+            &LOGIN_URL.to_string().into(),
+            &HOMEPAGE_URL.to_string().into(),
+        );
+
+        // Verify
+        assert!(result.is_ok());
+        assert_eq!("fixture-csrf-token", dao.into_session_data().csrf_token);
+    }
+
+    #[test]
+    fn test_logout() {
+        // Setup
+        let logout_url = "https://atcoder.jp/logout";
+        let http_handler = FixtureTransport::new()
+            .route_post(logout_url, "homepage.html")
+            .into_http_handler();
+        let dao = Dao::new(http_handler, "fixture-csrf-token".to_string());
+
+        // Run
+        let result = dao.logout(
+            &logout_url.to_string().into(),
+            &HOMEPAGE_URL.to_string().into(),
+        );
+
+        // Verify
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_logout_with_stale_csrf_token() {
+        // Setup
+        let logout_url = "https://atcoder.jp/logout";
+        let http_handler = FixtureTransport::new()
+            .route_get(HOMEPAGE_URL, "homepage.html")
+            .route_post(logout_url, "homepage.html")
+            .reject_csrf_token("stale-csrf-token")
+            .into_http_handler();
+        let dao = Dao::new(http_handler, "stale-csrf-token".to_string());
 
         // Run
-        let url = "https:://atcoder.jp/login".to_string().into();
-        let response = dao.login(Credentials { username, password }, &url);
+        let result = dao.logout(
+            &logout_url.to_string().into(),
+            &HOMEPAGE_URL.to_string().into(),
+        );
 
         // Verify
-        assert!(response.is_ok())
+        assert!(result.is_ok());
+        assert_eq!("fixture-csrf-token", dao.into_session_data().csrf_token);
+    }
+
+    #[test]
+    fn test_login_failed() {
+        // Setup
+        let http_handler = FixtureTransport::new()
+            .route_post(LOGIN_URL, "login_failed.html")
+            .into_http_handler();
+        let dao = Dao::new(http_handler, "fixture-csrf-token".to_string());
+        let credentials = Credentials {
+            username: "fixture_user".to_string(),
+            password: "wrong password".to_string(),
+        };
+
+        // Run
+        let result = dao.login(
+            credentials,
+            &LOGIN_URL.to_string().into(),
+            &HOMEPAGE_URL.to_string().into(),
+        );
+
+        // Verify
+        assert!(matches!(result, Err(Error::LoginFailed)))
+    }
+
+    #[test]
+    fn test_check_login() {
+        // Setup
+        let logged_in_dao = Dao::new(
+            FixtureTransport::new()
+                .route_get(HOMEPAGE_URL, "homepage_logged_in.html")
+                .into_http_handler(),
+            "fixture-csrf-token".to_string(),
+        );
+        let logged_out_dao = Dao::new(
+            FixtureTransport::new()
+                .route_get(HOMEPAGE_URL, "homepage.html")
+                .into_http_handler(),
+            "fixture-csrf-token".to_string(),
+        );
+        let url = HOMEPAGE_URL.to_string().into();
+
+        // Run
+        let logged_in = logged_in_dao.check_login(&url).unwrap();
+        let logged_out = logged_out_dao.check_login(&url).unwrap();
+
+        // Verify
+        assert!(logged_in);
+        assert!(!logged_out);
+        assert_eq!(Some("fixture_user".to_string()), logged_in_dao.username());
+        assert_eq!(None, logged_out_dao.username());
     }
 
     #[test]
-    #[ignore]
     fn test_fetch_test_suite_task_page() {
         // Setup
-        let http_handler = HttpHandler::new(Agent::new());
-        let task_url = "https://atcoder.jp/contests/abc388/tasks/abc388_a"
-            .to_string()
-            .into();
+        let task_url = "https://atcoder.jp/contests/abc388/tasks/abc388_a";
+        let http_handler = FixtureTransport::new()
+            .route_get(task_url, "task_page.html")
+            .into_http_handler();
         let dao = Dao::new(http_handler, "Dummy CSRF Token".to_string());
 
         // Run
-        let test_suite = dao.fetch_test_suite(&task_url).unwrap();
+        let test_suite = dao.fetch_test_suite(&task_url.to_string().into()).unwrap();
 
         // Verify
         println!("{test_suite:#?}");
         assert_eq!(1, test_suite.len());
+        assert_eq!("A", test_suite[0].task);
         assert_eq!(2, test_suite[0].test_cases.len());
+        assert_eq!("10 20\n", test_suite[0].test_cases[1].input);
+        assert_eq!("30\n", test_suite[0].test_cases[1].output);
     }
 
     #[test]
-    #[ignore]
     fn test_fetch_test_suite_tasks_print() {
         // Setup
-        let http_handler = HttpHandler::new(Agent::new());
-        let tasks_print_url = "https://atcoder.jp/contests/abc388/tasks_print"
-            .to_string()
-            .into();
+        let tasks_print_url = "https://atcoder.jp/contests/abc388/tasks_print";
+        let http_handler = FixtureTransport::new()
+            .route_get(tasks_print_url, "tasks_print.html")
+            .into_http_handler();
         let dao = Dao::new(http_handler, "Dummy CSRF Token".to_string());
 
         // Run
-        let test_suite = dao.fetch_test_suite(&tasks_print_url).unwrap();
+        let test_suite = dao
+            .fetch_test_suite(&tasks_print_url.to_string().into())
+            .unwrap();
 
         // Verify
         println!("{test_suite:#?}");
-        assert_eq!(7, test_suite.len());
+        assert_eq!(2, test_suite.len());
+        assert_eq!("B", test_suite[1].task);
     }
 
     #[test]
-    #[ignore]
     fn test_fetch_task_screen_names() {
         // Setup
-        let http_handler = HttpHandler::new(Agent::new());
-        let tasks_url = "https://atcoder.jp/contests/abc388/tasks"
-            .to_string()
-            .into();
+        let tasks_url = "https://atcoder.jp/contests/abc388/tasks";
+        let http_handler = FixtureTransport::new()
+            .route_get(tasks_url, "tasks.html")
+            .into_http_handler();
         let dao = Dao::new(http_handler, "Dummy CSRF Token".to_string());
 
         // Run
-        let task_screen_names = dao.fetch_task_screen_names(&tasks_url).unwrap();
+        let task_screen_names = dao
+            .fetch_task_screen_names(&tasks_url.to_string().into())
+            .unwrap();
 
         // Verify
-        assert_eq!(
-            vec![
-                "abc388_a", "abc388_b", "abc388_c", "abc388_d", "abc388_e", "abc388_f", "abc388_g"
-            ],
-            task_screen_names
+        assert_eq!(vec!["abc388_a", "abc388_b"], task_screen_names);
+    }
+
+    #[test]
+    fn test_fetch_contest_info() {
+        // Setup
+        let contest_url = "https://atcoder.jp/contests/abc388";
+        let http_handler = FixtureTransport::new()
+            .route_get(contest_url, "contest_page.html")
+            .route_get(&format!("{contest_url}/tasks"), "tasks.html")
+            .into_http_handler();
+        let dao = Dao::new(http_handler, "Dummy CSRF Token".to_string());
+
+        // Run
+        let contest_info = dao
+            .fetch_contest_info(&contest_url.to_string().into())
+            .unwrap();
+
+        // Verify
+        println!("{contest_info:#?}");
+        assert_eq!("AtCoder Beginner Contest 388", contest_info.title);
+        assert_eq!(100, contest_info.duration_minutes);
+        assert_eq!(Some("- 1999".to_string()), contest_info.rated_range);
+        assert_eq!(Some("5 minutes".to_string()), contest_info.penalty);
+        assert_eq!(2, contest_info.tasks.len());
+        assert_eq!(Some(200), contest_info.tasks[1].score);
+    }
+
+    #[test]
+    fn test_fetch_missing_page() {
+        // Setup
+        let http_handler = FixtureTransport::new().into_http_handler();
+        let dao = Dao::new(http_handler, "Dummy CSRF Token".to_string());
+
+        // Run
+        let result = dao.fetch_task_screen_names(
+            &"https://atcoder.jp/contests/abc388/tasks"
+                .to_string()
+                .into(),
         );
+
+        // Verify
+        assert!(matches!(
+            result,
+            Err(Error::HttpHandler(http_handler::Error::Status {
+                code: 404,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_fetch_without_login() {
+        // Setup
+        let task_url = "https://atcoder.jp/contests/abc388/tasks/abc388_a";
+        let http_handler = FixtureTransport::new()
+            .route_get(task_url, "login_page.html")
+            .into_http_handler();
+        let dao = Dao::new(http_handler, "Dummy CSRF Token".to_string());
+
+        // Run
+        let result = dao.fetch_test_suite(&task_url.to_string().into());
+
+        // Verify
+        assert!(matches!(result, Err(Error::NotLoggedIn)));
     }
 }
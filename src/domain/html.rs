@@ -1,9 +1,10 @@
 use super::page_type::{self, Tasks};
-use crate::dto::{TestCase, TestCases, TestSuite};
+use crate::dto::{TaskSummary, TestCase, TestCases, TestSuite};
 use itertools::Itertools;
 use regex::Regex;
 use scraper::{selectable::Selectable, ElementRef, Selector};
-use std::{marker::PhantomData, ops::Deref};
+use std::{collections::HashMap, marker::PhantomData, ops::Deref};
+use time::{macros::format_description, OffsetDateTime};
 
 pub struct Html<PageType>(scraper::Html, PhantomData<fn() -> PageType>);
 
@@ -22,15 +23,19 @@ impl<PageType> Deref for Html<PageType> {
     }
 }
 
-impl Html<page_type::Home> {
+impl<PageType> Html<PageType> {
+    /// From the form inputs, or the page script where no form is shown
     pub fn csrf_token(&self) -> Option<String> {
+        let regex = Regex::new(r#"var csrfToken = "([^"]+)""#).unwrap();
+
         self.select_one("[name=csrf_token]")
             .and_then(|element| element.attr("value"))
             .map(Into::into)
-    }
-
-    pub fn title(&self) -> Option<String> {
-        self.select_one("title").map(|element| element.inner_html())
+            .or_else(|| {
+                self.select_all("script")
+                    .into_iter()
+                    .find_map(|script| Some(regex.captures(&script.inner_html())?[1].to_string()))
+            })
     }
 
     pub fn has_sign_up_button(&self) -> bool {
@@ -38,6 +43,38 @@ impl Html<page_type::Home> {
             .map(|element| element.inner_html())
             .map_or(false, |name| name == "Sign Up")
     }
+
+    /// Whether nobody is logged in, including the sign-in page to which pages requiring login redirect
+    pub fn is_logged_out(&self) -> bool {
+        self.is_sign_in_page() || self.has_sign_up_button()
+    }
+
+    /// Whether this is the sign-in page, shown in place of a page requiring login
+    pub fn is_sign_in_page(&self) -> bool {
+        self.select_one("form input[name=password]").is_some()
+    }
+}
+
+impl Html<page_type::Home> {
+    pub fn title(&self) -> Option<String> {
+        self.select_one("title").map(|element| element.inner_html())
+    }
+
+    /// Screen name of the logged-in user, from the page script or the user menu in the navbar
+    pub fn username(&self) -> Option<String> {
+        let regex = Regex::new(r#"var userScreenName = "([^"]*)""#).unwrap();
+
+        self.select_all("script")
+            .into_iter()
+            .find_map(|script| Some(regex.captures(&script.inner_html())?[1].to_string()))
+            .filter(|username| !username.is_empty())
+            .or_else(|| {
+                self.select_one("#navbar-collapse > .navbar-right a[href^='/users/']")?
+                    .attr("href")?
+                    .strip_prefix("/users/")
+                    .map(Into::into)
+            })
+    }
 }
 
 impl Html<page_type::Task> {
@@ -118,7 +155,73 @@ impl TestCaseTag<'_> {
     }
 }
 
+impl Html<page_type::ContestHome> {
+    pub fn contest_title(&self) -> Option<String> {
+        self.select_one(".contest-title").map(text)
+    }
+
+    pub fn contest_duration(&self) -> Option<(OffsetDateTime, OffsetDateTime)> {
+        let format = format_description!(
+            "[year]-[month]-[day] [hour]:[minute]:[second][offset_hour][offset_minute]"
+        );
+
+        self.select_all("small.contest-duration time")
+            .into_iter()
+            .filter_map(|time| OffsetDateTime::parse(&text(time), format).ok())
+            .collect_tuple()
+    }
+
+    pub fn rated_range(&self) -> Option<String> {
+        self.contest_property(&["Rated Range", "Rated対象"])
+    }
+
+    pub fn penalty(&self) -> Option<String> {
+        self.contest_property(&["Penalty", "ペナルティ"])
+    }
+
+    /// Scores of tasks keyed by task label, taken from the score table in the contest statement
+    pub fn task_scores(&self) -> HashMap<String, u32> {
+        self.select_all("#contest-statement table > tbody > tr")
+            .into_iter()
+            .filter_map(|row| {
+                let (label, score) = row.select_all("td").into_iter().map(text).collect_tuple()?;
+                Some((label, score.parse().ok()?))
+            })
+            .collect()
+    }
+
+    fn contest_property(&self, keys: &[&str]) -> Option<String> {
+        self.select_all("span.mr-2").into_iter().find_map(|span| {
+            let (key, value) = text(span)
+                .split_once(':')
+                .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))?;
+            keys.contains(&key.as_str()).then_some(value)
+        })
+    }
+}
+
 impl Html<Tasks> {
+    pub fn tasks(&self) -> Vec<TaskSummary> {
+        let pattern = Regex::new(r"^/contests/[^/]+/tasks/([^/]+)$").unwrap();
+
+        self.select_all("table > tbody > tr")
+            .into_iter()
+            .filter_map(|row| {
+                let columns = row.select_all("td");
+                let task_url = columns.first()?.select_one("a")?.attr("href")?;
+
+                Some(TaskSummary {
+                    label: text(*columns.first()?),
+                    name: text(*columns.get(1)?),
+                    task_screen_name: pattern.captures(task_url)?.get(1)?.as_str().to_string(),
+                    score: None,
+                    time_limit: text(*columns.get(2)?),
+                    memory_limit: text(*columns.get(3)?),
+                })
+            })
+            .collect()
+    }
+
     pub fn task_screen_names(&self) -> Vec<String> {
         let pattern = Regex::new(r"^/contests/[^/]+/tasks/([^/]+)$").unwrap();
 
@@ -134,6 +237,10 @@ impl Html<Tasks> {
     }
 }
 
+fn text(element: ElementRef) -> String {
+    element.text().collect::<String>().trim().to_string()
+}
+
 trait Select<'a> {
     fn select_one(self, selectors: &str) -> Option<ElementRef<'a>>;
     fn select_all(self, selectors: &str) -> Vec<ElementRef<'a>>;
@@ -159,7 +266,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{handler::http_handler::HttpHandler, utils};
+    use crate::utils;
 
     #[test]
     #[ignore]
@@ -258,6 +365,82 @@ mod tests {
         assert_eq!(7, task_screen_names.len());
     }
 
+    #[test]
+    fn test_username() {
+        // Setup
+        let logged_in =
+            utils::test::load_fixture_html::<page_type::Home>("homepage_logged_in.html");
+        let logged_out = utils::test::load_fixture_html::<page_type::Home>("homepage.html");
+        let navbar_only: Html<page_type::Home> = r#"<div id="navbar-collapse"><ul class="navbar-right"><li><a href="/users/navbar_user">My Profile</a></li></ul></div>"#
+            .to_string()
+            .into();
+
+        // Run
+        let usernames = [&logged_in, &logged_out, &navbar_only].map(Html::username);
+
+        // Verify
+        assert_eq!(
+            [
+                Some("fixture_user".to_string()),
+                None,
+                Some("navbar_user".to_string())
+            ],
+            usernames
+        );
+    }
+
+    #[test]
+    fn test_tasks() {
+        // Setup
+        let html = utils::test::load_fixture_html::<page_type::Tasks>("tasks.html");
+
+        // Run
+        let tasks = html.tasks();
+
+        // Verify
+        println!("{tasks:#?}");
+        assert_eq!(2, tasks.len());
+        assert_eq!("A", tasks[0].label);
+        assert_eq!("abc388_a", tasks[0].task_screen_name);
+    }
+
+    #[test]
+    fn test_contest_title() {
+        // Setup
+        let html = utils::test::load_fixture_html::<page_type::ContestHome>("contest_page.html");
+
+        // Run
+        let title = html.contest_title();
+
+        // Verify
+        assert!(title.is_some_and(|title| title.contains("388")));
+    }
+
+    #[test]
+    fn test_contest_duration() {
+        // Setup
+        let html = utils::test::load_fixture_html::<page_type::ContestHome>("contest_page.html");
+
+        // Run
+        let (start_time, end_time) = html.contest_duration().expect("Contest Duration Not Found");
+
+        // Verify
+        assert_eq!(100, (end_time - start_time).whole_minutes());
+    }
+
+    #[test]
+    fn test_task_scores() {
+        // Setup
+        let html = utils::test::load_fixture_html::<page_type::ContestHome>("contest_page.html");
+
+        // Run
+        let task_scores = html.task_scores();
+
+        // Verify
+        println!("{task_scores:#?}");
+        assert_eq!(Some(&100), task_scores.get("A"));
+    }
+
     #[test]
     fn test_sign_up_button_before_login() {
         // Setup
@@ -271,11 +454,11 @@ mod tests {
     }
 
     #[test]
-    #[ignore]
     fn test_sign_up_button_after_login() {
         // Setup
-        let session_data = utils::test::load_session_data();
-        let http_handler = HttpHandler::with_cookies(session_data.cookies);
+        let http_handler = utils::test::load_cassette("homepage_after_login", || {
+            utils::test::load_session_data().cookies
+        });
 
         // Run
         let html: Html<page_type::Home> = http_handler
@@ -2,3 +2,4 @@ pub mod html;
 pub mod page_type;
 pub mod path;
 pub mod url;
+pub mod variables;
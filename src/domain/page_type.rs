@@ -12,3 +12,9 @@ pub struct Tasks;
 
 #[derive(Clone)]
 pub struct Login;
+
+#[derive(Clone)]
+pub struct Logout;
+
+#[derive(Clone)]
+pub struct Contests;
@@ -5,6 +5,80 @@ use std::{
     path::{Path, PathBuf},
 };
 
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Whether a profile name can be put into a file name, without path separators or dots
+pub fn is_valid_profile(profile: &str) -> bool {
+    !profile.is_empty() && !profile.contains(['/', '\\', '.'])
+}
+
+/// Session data files of profiles, e.g. `session_data.json` for the default profile and `session_data.alt.json` for `alt`
+pub struct SessionDataPath {
+    path: PathBuf,
+}
+
+impl SessionDataPath {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn profile_file(&self, profile: &str) -> PathBuf {
+        if profile == DEFAULT_PROFILE {
+            return self.path.clone();
+        }
+
+        let (stem, extension) = self.stem_and_extension();
+        self.path
+            .with_file_name(format!("{stem}.{profile}.{extension}"))
+    }
+
+    /// Gitignore pattern matching the files of all profiles
+    pub fn gitignore_pattern(&self) -> String {
+        let (stem, extension) = self.stem_and_extension();
+        let file = format!("{stem}*.{extension}");
+        match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => format!("/{}/{file}", dir.display()),
+            _ => format!("/{file}"),
+        }
+    }
+
+    /// Profiles which have a session data file
+    pub fn profiles(&self) -> Vec<String> {
+        let dir = match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let (stem, extension) = self.stem_and_extension();
+
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+
+        entries
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .filter_map(|file| {
+                if file == format!("{stem}.{extension}") {
+                    return Some(DEFAULT_PROFILE.to_string());
+                }
+                let profile = file
+                    .strip_prefix(&format!("{stem}."))?
+                    .strip_suffix(&format!(".{extension}"))?;
+                (!profile.is_empty() && !profile.contains('.')).then(|| profile.to_string())
+            })
+            .sorted()
+            .collect()
+    }
+
+    fn stem_and_extension(&self) -> (String, String) {
+        let stem = self.path.file_stem().unwrap_or_default();
+        let extension = self.path.extension().unwrap_or_default();
+        (
+            stem.to_string_lossy().to_string(),
+            extension.to_string_lossy().to_string(),
+        )
+    }
+}
+
 pub struct TaskTestPath {
     path: PathBuf,
 }
@@ -57,6 +131,48 @@ fn is_file(entry: &DirEntry) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::test::temp_dir;
+
+    #[test]
+    fn test_is_valid_profile() {
+        assert!(is_valid_profile("alt"));
+        assert!(is_valid_profile("alt-2_x"));
+        assert!(!is_valid_profile(""));
+        assert!(!is_valid_profile("../../x"));
+        assert!(!is_valid_profile("a/b"));
+        assert!(!is_valid_profile("a\\b"));
+        assert!(!is_valid_profile(".."));
+    }
+
+    #[test]
+    fn test_profile_file() {
+        // Setup
+        let session_data_path = SessionDataPath::new(".atcoder/session_data.json");
+
+        // Run
+        let default = session_data_path.profile_file(DEFAULT_PROFILE);
+        let alt = session_data_path.profile_file("alt");
+
+        // Verify
+        assert_eq!(PathBuf::from(".atcoder/session_data.json"), default);
+        assert_eq!(PathBuf::from(".atcoder/session_data.alt.json"), alt);
+    }
+
+    #[test]
+    fn test_profiles() {
+        // Setup
+        let dir = temp_dir("profiles");
+        for file in ["session_data.json", "session_data.alt.json", "other.json"] {
+            fs::write(dir.join(file), "{}").unwrap();
+        }
+        let session_data_path = SessionDataPath::new(dir.join("session_data.json"));
+
+        // Run
+        let profiles = session_data_path.profiles();
+
+        // Verify
+        assert_eq!(vec!["alt", "default"], profiles);
+    }
 
     #[test]
     #[ignore]
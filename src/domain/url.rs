@@ -21,6 +21,23 @@ impl<PageType> Deref for Url<PageType> {
     }
 }
 
+impl Url<page_type::Contests> {
+    pub fn contest_url(&self, contest: &str) -> Url<page_type::ContestHome> {
+        format!("{}/{contest}", self.0).into()
+    }
+}
+
+impl Url<page_type::ContestHome> {
+    /// Contest ID, the last path segment (e.g. abc388)
+    pub fn contest(&self) -> &str {
+        self.0.rsplit('/').next().unwrap_or_default()
+    }
+
+    pub fn tasks_url(&self) -> Url<page_type::Tasks> {
+        format!("{}/tasks", self.0).into()
+    }
+}
+
 #[derive(Clone)]
 pub enum FetchTaskUrl {
     Contest {
@@ -90,6 +107,19 @@ pub enum Error {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_contest() {
+        // Setup
+        let contest_url: Url<page_type::ContestHome> =
+            "https://atcoder.jp/contests/abc388".to_string().into();
+
+        // Run
+        let contest = contest_url.contest();
+
+        // Verify
+        assert_eq!("abc388", contest);
+    }
+
     #[test]
     fn test_parse_contest_homepage_url() {
         // Setup
@@ -139,6 +169,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_contest_url() {
+        // Setup
+        let contests_url: Url<page_type::Contests> =
+            "https://atcoder.jp/contests".to_string().into();
+
+        // Run
+        let contest_url = contests_url.contest_url("abc388");
+        let tasks_url = contest_url.tasks_url();
+
+        // Verify
+        assert_eq!("https://atcoder.jp/contests/abc388", *contest_url);
+        assert_eq!("https://atcoder.jp/contests/abc388/tasks", *tasks_url);
+    }
+
     #[test]
     fn fail_with_invalid_url() {
         // Setup
@@ -0,0 +1,144 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// Values substituted for `{name}` placeholders and `$NAME` or `${NAME}` environment variables
+///
+/// `{{`, `}}` and `$$` are substituted with the literal characters.
+#[derive(Clone)]
+pub struct Variables {
+    placeholders: HashMap<&'static str, String>,
+    env: HashMap<String, String>,
+}
+
+impl Variables {
+    pub fn new(env: impl IntoIterator<Item = (String, String)>) -> Self {
+        Self {
+            placeholders: HashMap::new(),
+            env: env.into_iter().collect(),
+        }
+    }
+
+    pub fn with(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.placeholders.insert(name, value.into());
+        self
+    }
+
+    pub fn expand(&self, template: &str) -> Result<String, Error> {
+        let mut expanded = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' | '}' | '$' if chars.next_if_eq(&c).is_some() => expanded.push(c),
+                '{' => {
+                    let name = take_until(&mut chars, '}')
+                        .ok_or_else(|| Error::Unclosed(template.to_string()))?;
+                    let value = self
+                        .placeholders
+                        .get(name.as_str())
+                        .ok_or(Error::UnknownPlaceholder(name))?;
+                    expanded.push_str(value);
+                }
+                '}' => return Err(Error::Unopened(template.to_string())),
+                '$' if chars.next_if_eq(&'{').is_some() => {
+                    let name = take_until(&mut chars, '}')
+                        .ok_or_else(|| Error::Unclosed(template.to_string()))?;
+                    expanded.push_str(self.env(name)?);
+                }
+                '$' if chars.peek().is_some_and(|&c| is_identifier(c)) => {
+                    let mut name = String::new();
+                    while let Some(c) = chars.next_if(|&c| is_identifier(c)) {
+                        name.push(c);
+                    }
+                    expanded.push_str(self.env(name)?);
+                }
+                c => expanded.push(c),
+            }
+        }
+
+        Ok(expanded)
+    }
+
+    pub fn expand_path(&self, template: &Path) -> Result<PathBuf, Error> {
+        let template = template
+            .to_str()
+            .ok_or_else(|| Error::NonUtf8Path(template.to_path_buf()))?;
+        Ok(self.expand(template)?.into())
+    }
+
+    fn env(&self, name: String) -> Result<&str, Error> {
+        self.env
+            .get(&name)
+            .map(String::as_str)
+            .ok_or(Error::UndefinedEnv(name))
+    }
+}
+
+/// Whether a template has anything to be substituted, so that it is not a literal value
+pub fn has_variables(template: &str) -> bool {
+    template.contains(['{', '$'])
+}
+
+fn take_until(chars: &mut impl Iterator<Item = char>, end: char) -> Option<String> {
+    let mut taken = String::new();
+    for c in chars {
+        if c == end {
+            return Some(taken);
+        }
+        taken.push(c);
+    }
+    None
+}
+
+fn is_identifier(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Unknown Placeholder: {{{0}}}")]
+    UnknownPlaceholder(String),
+
+    #[error("Undefined Environment Variable: ${0}")]
+    UndefinedEnv(String),
+
+    #[error("Unclosed Brace: {0}")]
+    Unclosed(String),
+
+    #[error("Unopened Brace: {0}")]
+    Unopened(String),
+
+    #[error("Non UTF-8 Path: {}", .0.display())]
+    NonUtf8Path(PathBuf),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        // Setup
+        let variables = Variables::new([("HOME".to_string(), "/home/user".to_string())])
+            .with("task", "a")
+            .with("contest", "abc388");
+
+        // Run
+        let expanded = variables.expand("src/bin/{task}.rs");
+        let env = variables.expand("$HOME/{contest}/${HOME}");
+        let escaped = variables.expand("{{task}} $$ $ a$");
+        let unknown = variables.expand("{unknown}");
+        let undefined = variables.expand("$UNDEFINED");
+        let unclosed = variables.expand("{task");
+
+        // Verify
+        assert_eq!("src/bin/a.rs", expanded.unwrap());
+        assert_eq!("/home/user/abc388//home/user", env.unwrap());
+        assert_eq!("{task} $ $ a$", escaped.unwrap());
+        assert!(matches!(unknown, Err(Error::UnknownPlaceholder(name)) if name == "unknown"));
+        assert!(matches!(undefined, Err(Error::UndefinedEnv(name)) if name == "UNDEFINED"));
+        assert!(matches!(unclosed, Err(Error::Unclosed(_))));
+    }
+}
@@ -1,8 +1,11 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::domain::{page_type, url};
+use crate::domain::{
+    page_type, url,
+    variables::{self, Variables},
+};
 use crate::dto::Command;
-use std::path::PathBuf;
+use std::path::{self, PathBuf};
 pub struct Config {
     pub app_config: AppConfig,
     pub user_config: UserConfig,
@@ -17,34 +20,218 @@ pub struct AppConfig {
 #[derive(Deserialize)]
 pub struct Path {
     pub session_data: PathBuf,
+    /// Relative to `$XDG_DATA_HOME`
+    pub global_session_data: PathBuf,
     pub tasks_info: PathBuf,
     pub test: PathBuf,
     pub user_config: PathBuf,
+    /// Relative to `$XDG_CONFIG_HOME`
+    pub global_user_config: PathBuf,
     pub metadata: PathBuf,
+    pub cache: PathBuf,
+    pub compile_hash: PathBuf,
 }
 
 #[derive(Deserialize)]
 pub struct Url {
     pub homepage: url::Url<page_type::Home>,
     pub login: url::Url<page_type::Login>,
+    pub logout: url::Url<page_type::Logout>,
+    pub contests: url::Url<page_type::Contests>,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct UserConfig {
+    /// Language used by `test` when it is neither given nor inferred from the sources
+    pub default_language: Option<String>,
+    #[serde(default)]
     language: Vec<LanguageConfig>,
+    #[serde(default)]
+    pub http: HttpConfig,
+    #[serde(default)]
+    pub session: SessionConfig,
+    #[serde(default)]
+    pub login: LoginConfig,
 }
 
 impl UserConfig {
     pub fn language_config(&self, language: &str) -> Option<&LanguageConfig> {
         self.language.iter().find(|config| config.name == language)
     }
+
+    pub fn languages(&self) -> &[LanguageConfig] {
+        &self.language
+    }
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoginConfig {
+    /// Prints the password on the first line, e.g. `pass show atcoder`
+    pub password_command: Option<Command>,
+    /// Log in again interactively and retry when the session has expired
+    pub relogin: bool,
+}
+
+impl Default for LoginConfig {
+    fn default() -> Self {
+        Self {
+            password_command: None,
+            relogin: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct LanguageConfig {
-    name: String,
+    pub name: String,
     id: String,
-    src_path: PathBuf,
+    pub src_path: PathBuf,
     pub compile: Option<Command>,
     pub execute: Command,
+    /// Prints the compiler version, which is part of the compile hash
+    pub version: Option<Command>,
+    /// Files or globs compiled with `src_path`, e.g. local headers or `Cargo.toml`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compile_inputs: Vec<PathBuf>,
+    /// Program built by `compile`, taken from `execute` if it runs a path such as `./a.out`
+    pub compile_output: Option<PathBuf>,
+}
+
+impl LanguageConfig {
+    /// Program built by `compile`, or `None` if unknown, e.g. for `java Main`
+    pub fn compiled_program(&self) -> Option<PathBuf> {
+        if let Some(output) = &self.compile_output {
+            return Some(output.clone());
+        }
+
+        let Command {
+            command,
+            working_dir,
+            shell,
+            ..
+        } = &self.execute;
+        let program = path::Path::new(command);
+        if *shell || program.components().count() < 2 {
+            return None;
+        }
+
+        Some(match working_dir {
+            Some(dir) => dir.join(program),
+            None => program.to_path_buf(),
+        })
+    }
+
+    /// Substitutes the variables in `src_path` and the commands
+    ///
+    /// `{src}` and `{bin}`, the file stem of the source, are taken from the expanded `src_path`.
+    pub fn expand(&self, variables: Variables) -> Result<Self, variables::Error> {
+        let src_path = variables.expand_path(&self.src_path)?;
+        let bin = src_path.file_stem().unwrap_or_default().to_string_lossy();
+        let variables = variables
+            .with("src", src_path.to_string_lossy())
+            .with("bin", bin);
+
+        Ok(Self {
+            name: self.name.clone(),
+            id: self.id.clone(),
+            compile: self
+                .compile
+                .as_ref()
+                .map(|command| command.expand(&variables))
+                .transpose()?,
+            execute: self.execute.expand(&variables)?,
+            version: self
+                .version
+                .as_ref()
+                .map(|command| command.expand(&variables))
+                .transpose()?,
+            compile_inputs: self
+                .compile_inputs
+                .iter()
+                .map(|path| variables.expand_path(path))
+                .collect::<Result<_, _>>()?,
+            compile_output: self
+                .compile_output
+                .as_ref()
+                .map(|path| variables.expand_path(path))
+                .transpose()?,
+            src_path,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct HttpConfig {
+    /// Number of retries for GET requests failing with 5xx, 429, timeout or connection errors
+    pub retries: u32,
+    /// Delay before the first retry, doubled on each subsequent retry
+    pub backoff_ms: u64,
+    /// Minimum delay between two consecutive requests
+    pub min_interval_ms: u64,
+    pub connect_timeout_secs: u64,
+    pub read_timeout_secs: u64,
+    /// Cache task, tasks list and contest pages on disk
+    pub cache: bool,
+    /// Serve every read from the cache and never touch the network
+    #[serde(skip)]
+    pub offline: bool,
+    /// Fixture file into which HTTP exchanges are recorded
+    #[serde(skip)]
+    pub record: Option<PathBuf>,
+    /// Fixture file from which HTTP exchanges are replayed instead of accessing the network
+    #[serde(skip)]
+    pub replay: Option<PathBuf>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            backoff_ms: 500,
+            min_interval_ms: 500,
+            connect_timeout_secs: 10,
+            read_timeout_secs: 30,
+            cache: true,
+            offline: false,
+            record: None,
+            replay: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    pub store: StoreKind,
+    /// Save new sessions to the per-user location shared across projects
+    pub global: bool,
+    /// Profile used when `--profile` is not given
+    pub profile: Option<String>,
+    /// Warn when the session expires within this many days
+    pub expiry_warning_days: i64,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            store: StoreKind::default(),
+            global: false,
+            profile: None,
+            expiry_warning_days: 7,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum StoreKind {
+    /// Plaintext JSON file with 0600 permissions
+    #[default]
+    File,
+    /// JSON file encrypted with a passphrase
+    Encrypted,
+    /// Secret Service keyring
+    Keyring,
 }
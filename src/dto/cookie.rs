@@ -1,4 +1,9 @@
-use cookie_store::{Cookie, CookieStore};
+use cookie_store::{Cookie, CookieStore, RawCookie};
+use serde::Deserialize;
+use time::OffsetDateTime;
+
+const DOMAIN: &str = "atcoder.jp";
+const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
 
 pub trait IntoCookieStore {
     fn into_cookie_store(self) -> CookieStore;
@@ -11,6 +16,128 @@ impl IntoCookieStore for Vec<Cookie<'static>> {
     }
 }
 
+/// Cookie in the JSON format of browser extensions such as Cookie-Editor
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportedCookie {
+    domain: String,
+    name: String,
+    value: String,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    secure: bool,
+    #[serde(default)]
+    http_only: bool,
+    #[serde(default)]
+    host_only: bool,
+    #[serde(default, alias = "expires")]
+    expiration_date: Option<f64>,
+}
+
+impl ExportedCookie {
+    fn is_atcoder(&self) -> bool {
+        self.domain.trim_start_matches('.') == DOMAIN
+    }
+
+    fn into_cookie(self) -> Result<Cookie<'static>, Error> {
+        let mut raw_cookie = RawCookie::build((self.name, self.value))
+            .path(self.path.unwrap_or_else(|| "/".to_string()))
+            .secure(self.secure)
+            .http_only(self.http_only);
+
+        if !self.host_only {
+            raw_cookie = raw_cookie.domain(self.domain.trim_start_matches('.').to_string());
+        }
+
+        // Session cookies are exported with no or non-positive expiration
+        if let Some(expiration_date) = self.expiration_date.filter(|&date| date > 0.0) {
+            let expires = OffsetDateTime::from_unix_timestamp(expiration_date as i64)
+                .map_err(|error| Error::InvalidCookie(error.to_string()))?;
+            raw_cookie = raw_cookie.expires(expires);
+        }
+
+        let url = url::Url::parse(&format!("https://{DOMAIN}/")).unwrap();
+        Cookie::try_from_raw_cookie(&raw_cookie.build(), &url)
+            .map(Cookie::into_owned)
+            .map_err(|error| Error::InvalidCookie(error.to_string()))
+    }
+}
+
+/// Parses atcoder.jp cookies exported from a browser, either in the Netscape `cookies.txt` format or as JSON
+pub fn parse_cookie_export(text: &str) -> Result<Vec<Cookie<'static>>, Error> {
+    let exported_cookies = if text.trim_start().starts_with(['[', '{']) {
+        parse_json(text)?
+    } else {
+        parse_netscape(text)?
+    };
+
+    exported_cookies
+        .into_iter()
+        .filter(ExportedCookie::is_atcoder)
+        .map(ExportedCookie::into_cookie)
+        .collect()
+}
+
+fn parse_json(text: &str) -> Result<Vec<ExportedCookie>, Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Export {
+        Cookies(Vec<ExportedCookie>),
+        Wrapped { cookies: Vec<ExportedCookie> },
+    }
+
+    match serde_json::from_str(text)? {
+        Export::Cookies(cookies) | Export::Wrapped { cookies } => Ok(cookies),
+    }
+}
+
+/// Tab separated fields: domain, include subdomains, path, secure, expiration, name and value
+fn parse_netscape(text: &str) -> Result<Vec<ExportedCookie>, Error> {
+    text.lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let (line, http_only) = match line.strip_prefix(HTTP_ONLY_PREFIX) {
+                Some(line) => (line, true),
+                None => (line, false),
+            };
+            if line.trim().is_empty() || line.starts_with('#') {
+                return None;
+            }
+
+            let fields = line.split('\t').collect::<Vec<_>>();
+            let [domain, include_subdomains, path, secure, expiration_date, name, value] =
+                fields[..]
+            else {
+                return Some(Err(Error::InvalidLine(i + 1)));
+            };
+
+            Some(Ok(ExportedCookie {
+                domain: domain.to_string(),
+                name: name.to_string(),
+                value: value.to_string(),
+                path: Some(path.to_string()),
+                secure: secure.eq_ignore_ascii_case("TRUE"),
+                http_only,
+                host_only: !include_subdomains.eq_ignore_ascii_case("TRUE"),
+                expiration_date: expiration_date.parse().ok(),
+            }))
+        })
+        .collect()
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error("Invalid Line {}", .0)]
+    InvalidLine(usize),
+
+    #[error("Invalid Cookie: {}", .0)]
+    InvalidCookie(String),
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -35,4 +162,51 @@ mod tests {
 
         assert_eq!(session_data.cookies, cookies);
     }
+
+    #[test]
+    fn test_parse_netscape_cookie_export() {
+        // Setup
+        let text = [
+            "# Netscape HTTP Cookie File",
+            "#HttpOnly_atcoder.jp\tFALSE\t/\tTRUE\t4102444800\tREVEL_SESSION\tsession",
+            ".atcoder.jp\tTRUE\t/\tFALSE\t0\tlanguage\tja",
+            ".example.com\tTRUE\t/\tFALSE\t0\tother\tvalue",
+        ]
+        .join("\n");
+
+        // Run
+        let cookies = parse_cookie_export(&text).unwrap();
+
+        // Verify
+        assert_eq!(2, cookies.len());
+        assert_eq!("REVEL_SESSION", cookies[0].name());
+        assert_eq!(Some(true), cookies[0].http_only());
+        assert!(cookies[0].expires_datetime().is_some());
+        assert_eq!("ja", cookies[1].value());
+    }
+
+    #[test]
+    fn test_parse_json_cookie_export() {
+        // Setup
+        let text = r#"[
+            {"domain": "atcoder.jp", "name": "REVEL_SESSION", "value": "session", "hostOnly": true, "httpOnly": true, "expirationDate": 4102444800.5},
+            {"domain": ".example.com", "name": "other", "value": "value"}
+        ]"#;
+
+        // Run
+        let cookies = parse_cookie_export(text).unwrap();
+
+        // Verify
+        assert_eq!(1, cookies.len());
+        assert_eq!("session", cookies[0].value());
+    }
+
+    #[test]
+    fn test_parse_invalid_netscape_line() {
+        // Run
+        let result = parse_cookie_export("atcoder.jp\tFALSE\t/");
+
+        // Verify
+        assert!(matches!(result, Err(Error::InvalidLine(1))));
+    }
 }
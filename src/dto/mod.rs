@@ -1,26 +1,76 @@
 pub mod config;
 pub mod cookie;
 
-use crate::domain::{page_type, url::Url};
+use crate::domain::{
+    page_type,
+    url::Url,
+    variables::{self, Variables},
+};
 use ::time::OffsetDateTime;
 use cookie_store::Cookie;
-use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use serde::{
+    de::{
+        self,
+        value::{MapAccessDeserializer, SeqAccessDeserializer},
+        MapAccess, SeqAccess, Visitor,
+    },
+    Deserialize, Deserializer, Serialize,
+};
+use std::{collections::BTreeMap, fmt, path::PathBuf};
 
 #[derive(Serialize, Deserialize)]
 pub struct SessionData {
     pub cookies: Vec<Cookie<'static>>,
     pub csrf_token: String,
+    #[serde(default)]
+    pub username: Option<String>,
 }
 
+/// Cookie which holds the login session
+pub const SESSION_COOKIE: &str = "REVEL_SESSION";
+
 impl SessionData {
     pub fn expired_datetime(&self) -> Option<OffsetDateTime> {
         self.cookies
             .iter()
-            .find_map(|cookie| cookie.expires_datetime())
+            .find(|cookie| cookie.name() == SESSION_COOKIE)
+            .and_then(|cookie| cookie.expires_datetime())
     }
 }
 
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub location: Option<Location>,
+    pub message: String,
+    pub help: Option<String>,
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// Position in a file, both 1-based
+#[derive(PartialEq, Eq, Debug)]
+pub struct Location {
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Serialize, Debug)]
+pub struct LoginStatus {
+    pub profile: String,
+    pub session: String,
+    pub logged_in: bool,
+    pub username: Option<String>,
+    #[serde(with = "::time::serde::rfc3339::option")]
+    pub expires_at: Option<OffsetDateTime>,
+    /// Whether the session expires within `expiry_warning_days`
+    pub expires_soon: bool,
+}
+
 #[derive(Debug)]
 pub struct TestCase {
     pub input: String,
@@ -48,16 +98,384 @@ pub struct TaskInfo {
     pub task_screen_name: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub body: String,
+}
+
+/// Request and response pair recorded by `RecordingTransport`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Exchange {
+    pub method: String,
+    pub url: String,
+    pub form: Vec<(String, String)>,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ContestInfo {
+    pub title: String,
+    #[serde(with = "::time::serde::rfc3339")]
+    pub start_time: OffsetDateTime,
+    #[serde(with = "::time::serde::rfc3339")]
+    pub end_time: OffsetDateTime,
+    pub duration_minutes: i64,
+    pub rated_range: Option<String>,
+    pub penalty: Option<String>,
+    pub tasks: Vec<TaskSummary>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TaskSummary {
+    pub label: String,
+    pub name: String,
+    pub task_screen_name: String,
+    pub score: Option<u32>,
+    pub time_limit: String,
+    pub memory_limit: String,
+}
+
+/// Command configured as a shell-like string, an array of words or a table
+///
+/// With `shell = true`, `command` is run by `sh -c` (`cmd /C` on Windows) and `args` are its positional parameters.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(try_from = "CommandForm")]
 pub struct Command {
     pub command: String,
     pub args: Vec<String>,
     pub working_dir: Option<PathBuf>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Start from an empty environment instead of inheriting it
+    #[serde(skip_serializing_if = "is_false")]
+    pub clear_env: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    pub shell: bool,
+}
+
+impl Command {
+    /// Substitutes the variables in the command, args, working directory and env
+    pub fn expand(&self, variables: &Variables) -> Result<Self, variables::Error> {
+        Ok(Self {
+            command: variables.expand(&self.command)?,
+            args: self
+                .args
+                .iter()
+                .map(|arg| variables.expand(arg))
+                .collect::<Result<_, _>>()?,
+            working_dir: self
+                .working_dir
+                .as_ref()
+                .map(|dir| variables.expand_path(dir))
+                .transpose()?,
+            env: self
+                .env
+                .iter()
+                .map(|(name, value)| Ok((name.clone(), variables.expand(value)?)))
+                .collect::<Result<_, _>>()?,
+            clear_env: self.clear_env,
+            shell: self.shell,
+        })
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+enum CommandForm {
+    Line(String),
+    Words(Vec<String>),
+    Table(CommandTable),
+}
+
+#[derive(Deserialize)]
+struct CommandTable {
+    command: String,
+    #[serde(default)]
+    args: Args,
+    working_dir: Option<PathBuf>,
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+    #[serde(default)]
+    clear_env: bool,
+    #[serde(default)]
+    shell: bool,
+}
+
+/// Deserialized by the type of the value, keeping the error of a malformed field of the table
+impl<'de> Deserialize<'de> for CommandForm {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct CommandFormVisitor;
+
+        impl<'de> Visitor<'de> for CommandFormVisitor {
+            type Value = CommandForm;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a shell-like string, an array of words or a table")
+            }
+
+            fn visit_str<E: de::Error>(self, line: &str) -> Result<Self::Value, E> {
+                Ok(CommandForm::Line(line.to_string()))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+                Deserialize::deserialize(SeqAccessDeserializer::new(seq)).map(CommandForm::Words)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                Deserialize::deserialize(MapAccessDeserializer::new(map)).map(CommandForm::Table)
+            }
+        }
+
+        deserializer.deserialize_any(CommandFormVisitor)
+    }
+}
+
+enum Args {
+    Line(String),
+    Words(Vec<String>),
+}
+
+impl<'de> Deserialize<'de> for Args {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ArgsVisitor;
+
+        impl<'de> Visitor<'de> for ArgsVisitor {
+            type Value = Args;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a shell-like string or an array of words")
+            }
+
+            fn visit_str<E: de::Error>(self, line: &str) -> Result<Self::Value, E> {
+                Ok(Args::Line(line.to_string()))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+                Deserialize::deserialize(SeqAccessDeserializer::new(seq)).map(Args::Words)
+            }
+        }
+
+        deserializer.deserialize_any(ArgsVisitor)
+    }
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self::Words(Vec::new())
+    }
+}
+
+impl TryFrom<Args> for Vec<String> {
+    type Error = String;
+
+    fn try_from(args: Args) -> Result<Self, Self::Error> {
+        match args {
+            Args::Line(line) => shlex::split(&line).ok_or(format!("invalid quoting in `{line}`")),
+            Args::Words(words) => Ok(words),
+        }
+    }
+}
+
+impl TryFrom<CommandForm> for Command {
+    type Error = String;
+
+    fn try_from(form: CommandForm) -> Result<Self, Self::Error> {
+        let words = match form {
+            CommandForm::Line(line) => Args::Line(line).try_into()?,
+            CommandForm::Words(words) => words,
+            CommandForm::Table(CommandTable {
+                command,
+                args,
+                working_dir,
+                env,
+                clear_env,
+                shell,
+            }) => {
+                return Ok(Self {
+                    command,
+                    args: args.try_into()?,
+                    working_dir,
+                    env,
+                    clear_env,
+                    shell,
+                })
+            }
+        };
+
+        let mut words = words.into_iter();
+        let command = words.next().ok_or("empty command")?;
+        Ok(Self {
+            command,
+            args: words.collect(),
+            ..Default::default()
+        })
+    }
+}
+
+/// Result of `test`, ordered from the best to the worst
+///
+/// Named after the verdicts of AtCoder's judge.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Verdict {
+    AC,
+    WA,
+    TLE,
+    RE,
+    CE,
+}
+
+impl Verdict {
+    /// Exit code of `test`, leaving 1 for errors of the tool itself
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Verdict::AC => 0,
+            Verdict::WA => 2,
+            Verdict::RE => 3,
+            Verdict::TLE => 4,
+            Verdict::CE => 5,
+        }
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
 }
 
+#[derive(Serialize)]
 pub struct Diff {
     pub input: String,
     pub expected: String,
+    /// Stdout of the program
     pub actual: String,
+    pub stderr: String,
+    #[serde(skip)]
     pub file: String,
 }
+
+/// Result of `test` for reports
+#[derive(Serialize)]
+pub struct TestReport {
+    pub task: String,
+    pub language: String,
+    pub verdict: Verdict,
+    pub compile: CompileResult,
+    pub test_cases: Vec<TestCaseResult>,
+}
+
+#[derive(Serialize)]
+pub struct CompileResult {
+    pub status: CompileStatus,
+    /// Diagnostics of the compiler, empty if it is not run
+    pub output: String,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum CompileStatus {
+    /// No compile command is configured
+    NotRequired,
+    /// Skipped since nothing has changed since the last compile
+    Cached,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Serialize)]
+pub struct TestCaseResult {
+    pub file: String,
+    pub verdict: Verdict,
+    /// Wall-clock time including the startup of the program
+    pub time_ms: u64,
+    /// Peak resident memory, unknown on Windows
+    pub memory_kib: Option<u64>,
+    /// Input, expected and actual output unless AC
+    #[serde(flatten)]
+    pub diff: Option<Diff>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Commands {
+        line: Command,
+        words: Command,
+        table: Command,
+        invalid: Option<Command>,
+    }
+
+    #[test]
+    fn test_deserialize_command() {
+        // Setup
+        let toml = r#"
+            line = "g++ -O2 -o 'a b.out' \"main.cpp\""
+            words = ["python3", "main.py"]
+            table = { command = "cargo", args = "run --bin a", env = { RUST_BACKTRACE = "1" }, clear_env = true }
+        "#;
+
+        // Run
+        let Commands {
+            line,
+            words,
+            table,
+            invalid,
+        } = toml::from_str(toml).unwrap();
+        let unclosed = toml::from_str::<Commands>(&format!("{toml}invalid = \"echo 'a\""));
+
+        // Verify
+        assert_eq!("g++", line.command);
+        assert_eq!(vec!["-O2", "-o", "a b.out", "main.cpp"], line.args);
+        assert_eq!("python3", words.command);
+        assert_eq!(vec!["main.py"], words.args);
+        assert_eq!("cargo", table.command);
+        assert_eq!(vec!["run", "--bin", "a"], table.args);
+        assert_eq!(
+            Some("1"),
+            table.env.get("RUST_BACKTRACE").map(String::as_str)
+        );
+        assert!(table.clear_env);
+        assert!(!table.shell);
+        assert!(invalid.is_none());
+        assert!(unclosed.is_err());
+    }
+
+    #[test]
+    fn test_deserialize_malformed_command() {
+        // Setup
+        let toml = |command| format!("line = \"a\"\nwords = [\"b\"]\ntable = {command}\n");
+
+        // Run
+        let env = toml::from_str::<Commands>(&toml(r#"{ command = "c", env = 1 }"#));
+        let args = toml::from_str::<Commands>(&toml(r#"{ command = "c", args = 1 }"#));
+        let number = toml::from_str::<Commands>(&toml("1"));
+
+        // Verify
+        let message = |result: Result<_, toml::de::Error>| result.err().unwrap().to_string();
+        let env = message(env);
+        assert!(env.contains("line 3, column 32"), "{env}");
+        assert!(
+            env.contains("invalid type: integer `1`, expected a map"),
+            "{env}"
+        );
+        let args = message(args);
+        assert!(args.contains("line 3, column 33"), "{args}");
+        assert!(
+            args.contains("expected a shell-like string or an array of words"),
+            "{args}"
+        );
+        assert!(
+            message(number).contains("expected a shell-like string, an array of words or a table")
+        );
+    }
+}
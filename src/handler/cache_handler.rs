@@ -0,0 +1,119 @@
+use super::file_handler::{self, WithPath};
+use crate::dto::CacheEntry;
+use std::{fs, io, path::PathBuf};
+
+pub struct ResponseCache {
+    dir: PathBuf,
+}
+
+impl ResponseCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn load(&self, url: &str) -> Option<CacheEntry> {
+        file_handler::load(&self.file(url)).ok()
+    }
+
+    pub fn save(&self, entry: &CacheEntry) -> Result<(), file_handler::Error> {
+        fs::create_dir_all(&self.dir).with_path(&self.dir)?;
+        file_handler::save(&self.file(&entry.url), entry)
+    }
+
+    fn file(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", cache_key(url)))
+    }
+}
+
+/// Hash of the inputs of the last successful compile, to skip compiling unchanged sources
+///
+/// Only the last one is kept, since languages and tasks may share the compiled program, e.g. `a.out`.
+pub struct CompileCache {
+    file: PathBuf,
+}
+
+impl CompileCache {
+    pub fn new(file: impl Into<PathBuf>) -> Self {
+        Self { file: file.into() }
+    }
+
+    pub fn is_fresh(&self, hash: &str) -> bool {
+        fs::read_to_string(&self.file).is_ok_and(|saved| saved == hash)
+    }
+
+    pub fn save(&self, hash: &str) -> Result<(), file_handler::Error> {
+        file_handler::save_text(&self.file, hash)
+    }
+
+    pub fn clear(&self) -> Result<(), file_handler::Error> {
+        match fs::remove_file(&self.file) {
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result.with_path(&self.file),
+        }
+    }
+}
+
+/// Encodes a URL into a file name, keeping it readable and free of collisions
+fn cache_key(url: &str) -> String {
+    let url = url.split_once("://").map_or(url, |(_, rest)| rest);
+
+    url.bytes()
+        .map(|byte| match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' => (byte as char).to_string(),
+            _ => format!("_{byte:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test::temp_dir;
+
+    #[test]
+    fn test_cache_key() {
+        assert_eq!(
+            "atcoder.jp_2Fcontests_2Fabc388_2Ftasks_2Fabc388_5Fa",
+            cache_key("https://atcoder.jp/contests/abc388/tasks/abc388_a")
+        );
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        // Setup
+        let cache = ResponseCache::new(temp_dir("response_cache"));
+        let entry = CacheEntry {
+            url: "https://atcoder.jp/contests/abc388/tasks".to_string(),
+            etag: Some("\"etag\"".to_string()),
+            last_modified: None,
+            body: "<html></html>".to_string(),
+        };
+
+        // Run
+        cache.save(&entry).unwrap();
+        let loaded = cache.load(&entry.url).expect("Cache Entry Not Found");
+
+        // Verify
+        assert_eq!(entry.etag, loaded.etag);
+        assert_eq!(entry.body, loaded.body);
+    }
+
+    #[test]
+    fn test_compile_cache() {
+        // Setup
+        let cache = CompileCache::new(temp_dir("compile_cache").join("compile_hash"));
+        cache.clear().unwrap();
+
+        // Run
+        let before_save = cache.is_fresh("hash");
+        cache.save("hash").unwrap();
+        let after_save = (cache.is_fresh("hash"), cache.is_fresh("other"));
+        cache.clear().unwrap();
+        let after_clear = cache.is_fresh("hash");
+
+        // Verify
+        assert!(!before_save);
+        assert_eq!((true, false), after_save);
+        assert!(!after_clear);
+    }
+}
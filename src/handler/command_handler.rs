@@ -1,34 +1,228 @@
 use crate::dto::Command;
 use std::{
-    io::Write,
-    process::{self, Stdio},
+    env,
+    io::{self, Read, Write},
+    path::PathBuf,
+    process::{self, Child, ExitStatus, Output, Stdio},
+    thread,
+    time::{Duration, Instant},
 };
 
-pub fn run<T: ReturnType>(
+/// Finds an executable in the directories of `$PATH`, as the shell does
+pub fn find_in_path(command: &str) -> Option<PathBuf> {
+    let extensions: &[&str] = if cfg!(windows) {
+        &["", ".exe", ".cmd", ".bat"]
+    } else {
+        &[""]
+    };
+
+    env::split_paths(&env::var_os("PATH")?)
+        .flat_map(|dir| {
+            extensions
+                .iter()
+                .map(move |extension| dir.join(format!("{command}{extension}")))
+        })
+        .find(|path| path.is_file())
+}
+
+pub fn run<T: ReturnType>(command: &Command, input: Option<&str>) -> Result<T, std::io::Error> {
+    let mut command = process_command(command);
+
+    // Stdin
+    if input.is_some() {
+        command.stdin(Stdio::piped());
+    }
+
+    let result = T::from_process(command, input)?;
+    Ok(result)
+}
+
+/// Output of a process run with a time limit
+pub struct Execution {
+    pub output: Output,
+    pub elapsed: Duration,
+    /// Peak resident memory in KiB, unknown on Windows
+    pub memory_kib: Option<u64>,
+    /// Killed on exceeding the time limit
+    pub timed_out: bool,
+}
+
+/// Runs a command and kills it with its child processes when it exceeds the time limit
+pub fn run_with_time_limit(
+    command: &Command,
+    input: &str,
+    time_limit: Duration,
+) -> Result<Execution, std::io::Error> {
+    let mut command = process_command(command);
+    command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // Its own process group, so that e.g. the program run by `cargo run` is killed too
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+
+    let start = Instant::now();
+    let mut child = command.spawn()?;
+
+    // Pipes are handled in threads not to block on a full one
+    let mut stdin = child.stdin.take().unwrap();
+    let input = input.to_string();
+    let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let stdout = read_in_thread(child.stdout.take().unwrap());
+    let stderr = read_in_thread(child.stderr.take().unwrap());
+
+    let ((status, memory_kib), timed_out) = wait_with_time_limit(&mut child, start + time_limit)?;
+    let elapsed = start.elapsed();
+
+    // The program may exit without reading the whole input
+    let _ = writer.join();
+    let output = Output {
+        status,
+        stdout: stdout.join().unwrap()?,
+        stderr: stderr.join().unwrap()?,
+    };
+
+    Ok(Execution {
+        output,
+        elapsed,
+        memory_kib,
+        timed_out,
+    })
+}
+
+fn read_in_thread(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<io::Result<Vec<u8>>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        pipe.read_to_end(&mut buffer)?;
+        Ok(buffer)
+    })
+}
+
+/// Exit status and peak resident memory in KiB
+type Exit = (ExitStatus, Option<u64>);
+
+fn wait_with_time_limit(child: &mut Child, deadline: Instant) -> io::Result<(Exit, bool)> {
+    loop {
+        if let Some(exit) = wait(child, false)? {
+            return Ok((exit, false));
+        }
+        if Instant::now() >= deadline {
+            kill(child)?;
+            let exit = wait(child, true)?.expect("Blocking wait returns the exit status");
+            return Ok((exit, true));
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+/// Waits for the child with `wait4`, which also reports its resource usage
+#[cfg(unix)]
+fn wait(child: &mut Child, block: bool) -> io::Result<Option<Exit>> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let flags = if block { 0 } else { libc::WNOHANG };
+    let mut status = 0;
+    // SAFETY: `rusage` is plain old data, for which all zeros is valid
+    let mut usage = unsafe { std::mem::zeroed::<libc::rusage>() };
+
+    loop {
+        // SAFETY: The child has not been reaped yet, so that its pid is not reused
+        let pid = unsafe { libc::wait4(child.id() as libc::pid_t, &mut status, flags, &mut usage) };
+        match pid {
+            0 => return Ok(None),
+            -1 => {
+                let error = io::Error::last_os_error();
+                if error.kind() != io::ErrorKind::Interrupted {
+                    return Err(error);
+                }
+            }
+            _ => {
+                // In bytes on macOS and in KiB on the others
+                let max_rss = usage.ru_maxrss as u64;
+                let memory_kib = if cfg!(target_os = "macos") {
+                    max_rss / 1024
+                } else {
+                    max_rss
+                };
+                return Ok(Some((ExitStatus::from_raw(status), Some(memory_kib))));
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+fn wait(child: &mut Child, block: bool) -> io::Result<Option<Exit>> {
+    let status = if block {
+        Some(child.wait()?)
+    } else {
+        child.try_wait()?
+    };
+    Ok(status.map(|status| (status, None)))
+}
+
+#[cfg(unix)]
+fn kill(child: &mut Child) -> io::Result<()> {
+    // SAFETY: Only sends a signal to the process group of the child, which has not been reaped yet
+    let result = unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(windows)]
+fn kill(child: &mut Child) -> io::Result<()> {
+    child.kill()
+}
+
+fn process_command(
     Command {
         command,
         args,
         working_dir,
+        env,
+        clear_env,
+        shell,
     }: &Command,
-    input: Option<&str>,
-) -> Result<T, std::io::Error> {
-    let mut command = process::Command::new(command);
+) -> process::Command {
+    let mut command = if *shell {
+        shell_command(command)
+    } else {
+        process::Command::new(command)
+    };
 
     // Args
     command.args(args);
 
+    // Environment variables
+    if *clear_env {
+        command.env_clear();
+    }
+    command.envs(env);
+
     // Working directory
     if let Some(working_dir) = working_dir {
         command.current_dir(working_dir);
     }
 
-    // Stdin
-    if input.is_some() {
-        command.stdin(Stdio::piped());
-    }
+    command
+}
 
-    let result = T::from_process(command, input)?;
-    Ok(result)
+/// Runs a script by the shell, whose positional parameters are given as args
+#[cfg(unix)]
+fn shell_command(script: &str) -> process::Command {
+    let mut command = process::Command::new("sh");
+    command.args(["-c", script, "sh"]);
+    command
+}
+
+#[cfg(windows)]
+fn shell_command(script: &str) -> process::Command {
+    let mut command = process::Command::new("cmd");
+    command.args(["/C", script]);
+    command
 }
 
 pub trait ReturnType {
@@ -90,7 +284,7 @@ mod tests {
         let command = Command {
             command: "echo".to_string(),
             args: vec!["Hello, World!".to_string()],
-            working_dir: None,
+            ..Default::default()
         };
 
         let result = run::<process::Output>(&command, None);
@@ -104,10 +298,58 @@ mod tests {
             command: "ls".to_string(),
             args: vec![],
             working_dir: Some(PathBuf::from_str("src").unwrap()),
+            ..Default::default()
         };
 
         let result = run::<process::Output>(&command, None);
         let output = String::from_utf8(result.unwrap().stdout).unwrap();
         assert!(output.split_whitespace().any(|file| file == "main.rs"));
     }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_run_with_time_limit() {
+        // Setup
+        let command = |script: &str| Command {
+            command: script.to_string(),
+            shell: true,
+            ..Default::default()
+        };
+        let time_limit = Duration::from_millis(500);
+
+        // Run
+        let finished = run_with_time_limit(&command("cat"), "input", time_limit).unwrap();
+        let start = Instant::now();
+        let timed_out =
+            run_with_time_limit(&command("sleep 10 & wait; echo done"), "", time_limit).unwrap();
+        let elapsed = start.elapsed();
+
+        // Verify
+        assert!(!finished.timed_out);
+        assert_eq!(b"input", &finished.output.stdout[..]);
+        assert!(finished.memory_kib.is_some_and(|memory| memory > 0));
+        assert!(timed_out.timed_out);
+        assert!(timed_out.output.stdout.is_empty());
+        assert!(elapsed < Duration::from_secs(5));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_run_with_shell_and_env() {
+        // Setup
+        let command = Command {
+            command: "echo \"$GREETING, $1\" && echo $HOME".to_string(),
+            args: vec!["World!".to_string()],
+            env: [("GREETING".to_string(), "Hello".to_string())].into(),
+            clear_env: true,
+            shell: true,
+            ..Default::default()
+        };
+
+        // Run
+        let output = run::<process::Output>(&command, None).unwrap();
+
+        // Verify
+        assert_eq!(b"Hello, World!\n\n", &output.stdout[..]);
+    }
 }
@@ -0,0 +1,725 @@
+use super::{
+    command_handler,
+    file_handler::{self, WithPath},
+    session_handler,
+};
+use crate::{
+    domain::variables,
+    dto::{
+        config::{AppConfig, Config, UserConfig},
+        Command, Diagnostic, Location, Severity,
+    },
+    utils::suggest,
+};
+use std::{
+    env, fs,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+use toml::{Table, Value};
+use toml_edit::{ImDocument, Item, TableLike};
+
+const ENV_PREFIX: &str = "ATCODER_TOOLS_";
+/// Separates sections from a key in environment variables, e.g. `ATCODER_TOOLS_HTTP__RETRIES`
+const ENV_SEPARATOR: &str = "__";
+
+/// Variables with the prefix which are read by the tool itself rather than settings
+const RESERVED_ENV: &[&str] = &[session_handler::PASSPHRASE_ENV];
+
+/// Commented config written by `init`
+pub const USER_CONFIG_TEMPLATE: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/user_config.toml"
+));
+
+pub fn load_app_config() -> Result<AppConfig, Error> {
+    let app_config = toml::from_str(include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/config.toml"
+    )))
+    .with_path(Path::new(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/config.toml"
+    )))?;
+    Ok(app_config)
+}
+
+pub fn load_config() -> Result<Config, Error> {
+    // Load config for app
+    let app_config = load_app_config()?;
+
+    enter_workspace(&app_config)?;
+
+    // Load config for user
+    let user_config = load_user_config(&user_config_paths(&app_config), env::vars())?;
+
+    Ok(Config {
+        app_config,
+        user_config,
+    })
+}
+
+/// Moves to the root directory for app, which has the metadata directory
+pub fn enter_workspace(app_config: &AppConfig) -> Result<(), Error> {
+    let metadata_path = file_handler::find_in_ancestors(&app_config.path.metadata)
+        .ok_or_else(|| Error::NotInitialized(app_config.path.metadata.clone()))?;
+    let root_path = metadata_path.parent().unwrap();
+    env::set_current_dir(root_path).with_path(root_path)?;
+    Ok(())
+}
+
+/// User config files from the lowest precedence: the per-user one, then the project one
+pub fn user_config_paths(app_config: &AppConfig) -> Vec<PathBuf> {
+    let global =
+        file_handler::config_home().map(|dir| dir.join(&app_config.path.global_user_config));
+    global
+        .into_iter()
+        .chain([app_config.path.user_config.clone()])
+        .collect()
+}
+
+/// Layers the existing config files and then environment variables over the built-in defaults
+fn load_user_config(
+    paths: &[PathBuf],
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Result<UserConfig, Error> {
+    let config = merge_layers(paths, vars)?;
+    Ok(Value::Table(config).try_into()?)
+}
+
+fn merge_layers(
+    paths: &[PathBuf],
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Result<Table, Error> {
+    let mut config = Table::new();
+
+    for path in paths.iter().filter(|path| path.exists()) {
+        let layer: Table = file_handler::load_toml(path)?;
+        merge(&mut config, layer);
+    }
+    merge(&mut config, env_overrides(vars));
+
+    Ok(config)
+}
+
+fn merge(base: &mut Table, overlay: Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base)), Value::Table(overlay)) => merge(base, overlay),
+            (Some(Value::Array(base)), Value::Array(overlay)) if key == "language" => {
+                merge_languages(base, overlay)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Languages are merged by name, so that a layer can override only a part of a language
+fn merge_languages(base: &mut Vec<Value>, overlay: Vec<Value>) {
+    for language in overlay {
+        let same_name = base
+            .iter_mut()
+            .find(|base| base.get("name").is_some() && base.get("name") == language.get("name"));
+
+        match (same_name, language) {
+            (Some(Value::Table(base)), Value::Table(overlay)) => merge(base, overlay),
+            (_, language) => base.push(language),
+        }
+    }
+}
+
+fn env_overrides(vars: impl IntoIterator<Item = (String, String)>) -> Table {
+    let mut overrides = Table::new();
+
+    for (name, value) in vars {
+        if RESERVED_ENV.contains(&name.as_str()) {
+            continue;
+        }
+        let Some(name) = name.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        let name = name.to_lowercase();
+        let keys = name.split(ENV_SEPARATOR).collect::<Vec<_>>();
+
+        // A string setting may look like a number, a bool or a date, e.g. a profile `1234`
+        let typed = nest(&keys, parse_env_value(&value));
+        let layer = if typed.clone().try_into::<UserConfig>().is_ok() {
+            typed
+        } else {
+            nest(&keys, Value::String(value))
+        };
+        merge(&mut overrides, layer);
+    }
+
+    overrides
+}
+
+/// Puts the value under the keys, e.g. `{ http = { retries = 3 } }`
+fn nest(keys: &[&str], value: Value) -> Table {
+    let (last, keys) = keys.split_last().unwrap();
+    keys.iter().rev().fold(
+        Table::from_iter([(last.to_string(), value)]),
+        |table, &key| Table::from_iter([(key.to_string(), Value::Table(table))]),
+    )
+}
+
+/// Parses a TOML value such as `3` or `true`, and takes anything else as a string
+fn parse_env_value(value: &str) -> Value {
+    format!("value = {value}")
+        .parse::<Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(value.to_string()))
+}
+
+/// Keys allowed in the user config
+enum Schema {
+    Value,
+    /// Also applies to each table of an array of tables
+    Table(&'static [(&'static str, Schema)]),
+}
+
+const COMMAND_KEYS: &[(&str, Schema)] = &[
+    ("command", Schema::Value),
+    ("args", Schema::Value),
+    ("working_dir", Schema::Value),
+    ("env", Schema::Value),
+    ("clear_env", Schema::Value),
+    ("shell", Schema::Value),
+];
+
+const USER_CONFIG_KEYS: &[(&str, Schema)] = &[
+    ("default_language", Schema::Value),
+    (
+        "language",
+        Schema::Table(&[
+            ("name", Schema::Value),
+            ("id", Schema::Value),
+            ("src_path", Schema::Value),
+            ("compile", Schema::Table(COMMAND_KEYS)),
+            ("execute", Schema::Table(COMMAND_KEYS)),
+            ("version", Schema::Table(COMMAND_KEYS)),
+            ("compile_inputs", Schema::Value),
+            ("compile_output", Schema::Value),
+        ]),
+    ),
+    (
+        "http",
+        Schema::Table(&[
+            ("retries", Schema::Value),
+            ("backoff_ms", Schema::Value),
+            ("min_interval_ms", Schema::Value),
+            ("connect_timeout_secs", Schema::Value),
+            ("read_timeout_secs", Schema::Value),
+            ("cache", Schema::Value),
+        ]),
+    ),
+    (
+        "session",
+        Schema::Table(&[
+            ("store", Schema::Value),
+            ("global", Schema::Value),
+            ("profile", Schema::Value),
+            ("expiry_warning_days", Schema::Value),
+        ]),
+    ),
+    (
+        "login",
+        Schema::Table(&[
+            ("password_command", Schema::Table(COMMAND_KEYS)),
+            ("relogin", Schema::Value),
+        ]),
+    ),
+];
+
+/// Config file parsed with the spans of its keys
+struct Document {
+    file: PathBuf,
+    document: ImDocument<String>,
+}
+
+impl Document {
+    fn location(&self, span: Option<Range<usize>>) -> Option<Location> {
+        location(&self.file, self.document.raw(), span?.start)
+    }
+}
+
+/// Validates the user config files and the config resolved from them
+pub fn check_user_config(paths: &[PathBuf]) -> Result<Vec<Diagnostic>, Error> {
+    let mut diagnostics = Vec::new();
+    let mut documents = Vec::new();
+
+    for file in paths.iter().filter(|path| path.exists()) {
+        let text = fs::read_to_string(file).with_path(file)?;
+        match ImDocument::parse(text.clone()) {
+            Ok(document) => {
+                let document = Document {
+                    file: file.clone(),
+                    document,
+                };
+                check_keys(
+                    &document,
+                    document.document.as_table(),
+                    USER_CONFIG_KEYS,
+                    None,
+                    &mut diagnostics,
+                );
+                documents.push(document);
+            }
+            Err(error) => diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                location: error
+                    .span()
+                    .and_then(|span| location(file, &text, span.start)),
+                message: error.message().trim().to_string(),
+                help: None,
+            }),
+        }
+    }
+
+    // The other checks need every file to be parsed
+    if documents.len() < paths.iter().filter(|path| path.exists()).count() {
+        return Ok(diagnostics);
+    }
+
+    let config = merge_layers(paths, env::vars())?;
+    check_languages(&config, &documents, &mut diagnostics);
+    if diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+    {
+        return Ok(diagnostics);
+    }
+
+    match Value::Table(config).try_into::<UserConfig>() {
+        Ok(user_config) => check_paths(&user_config, &documents, &mut diagnostics),
+        Err(error) => diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            location: None,
+            message: error.message().trim().to_string(),
+            help: None,
+        }),
+    }
+
+    Ok(diagnostics)
+}
+
+fn check_keys(
+    document: &Document,
+    table: &dyn TableLike,
+    keys: &[(&str, Schema)],
+    section: Option<&str>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for (key, item) in table.iter() {
+        match keys.iter().find(|&&(name, _)| name == key) {
+            Some((_, Schema::Table(keys))) => {
+                let section = match section {
+                    Some(section) => format!("{section}.{key}"),
+                    None => key.to_string(),
+                };
+                for table in tables(item) {
+                    check_keys(document, table, keys, Some(&section), diagnostics);
+                }
+            }
+            Some((_, Schema::Value)) => {}
+            None => {
+                let span = table.get_key_value(key).and_then(|(key, _)| key.span());
+                let message = match section {
+                    Some(section) => format!("unknown key `{key}` in `{section}`"),
+                    None => format!("unknown key `{key}`"),
+                };
+                diagnostics.push(Diagnostic {
+                    severity: Severity::Error,
+                    location: document.location(span),
+                    message,
+                    help: suggest::closest(key, keys.iter().map(|&(name, _)| name))
+                        .map(|name| format!("did you mean `{name}`?")),
+                });
+            }
+        }
+    }
+}
+
+fn tables(item: &Item) -> Vec<&dyn TableLike> {
+    match item {
+        Item::ArrayOfTables(array) => array.iter().map(|table| table as &dyn TableLike).collect(),
+        Item::Value(toml_edit::Value::Array(array)) => array
+            .iter()
+            .filter_map(|value| value.as_inline_table())
+            .map(|table| table as &dyn TableLike)
+            .collect(),
+        item => item.as_table_like().into_iter().collect(),
+    }
+}
+
+fn check_languages(config: &Table, documents: &[Document], diagnostics: &mut Vec<Diagnostic>) {
+    let languages = config
+        .get("language")
+        .and_then(Value::as_array)
+        .map_or(&[][..], Vec::as_slice);
+
+    for (i, language) in languages.iter().enumerate() {
+        let Some(name) = language.get("name").and_then(Value::as_str) else {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                location: None,
+                message: format!("language #{} has no `name`", i + 1),
+                help: None,
+            });
+            continue;
+        };
+
+        if language.get("execute").is_none() {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                location: locate_language(documents, name, "name"),
+                message: format!("language `{name}` has no `execute`"),
+                help: Some("add e.g. `execute = \"./a.out\"`".to_string()),
+            });
+        }
+    }
+
+    let names = languages
+        .iter()
+        .filter_map(|language| language.get("name").and_then(Value::as_str));
+    if let Some(default_language) = config.get("default_language").and_then(Value::as_str) {
+        if !names.clone().any(|name| name == default_language) {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                location: locate_key(documents, "default_language"),
+                message: format!("`default_language` `{default_language}` is not configured"),
+                help: suggest::closest(default_language, names)
+                    .map(|name| format!("did you mean `{name}`?")),
+            });
+        }
+    }
+}
+
+fn check_paths(
+    user_config: &UserConfig,
+    documents: &[Document],
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for language in user_config.languages() {
+        let name = &language.name;
+
+        // Paths with variables are known only when a task is tested
+        if !is_template(&language.src_path) && !language.src_path.exists() {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                location: locate_language(documents, name, "src_path"),
+                message: format!(
+                    "`src_path` of `{name}` does not exist: {}",
+                    language.src_path.display()
+                ),
+                help: None,
+            });
+        }
+
+        for pattern in &language.compile_inputs {
+            if let Err(file_handler::Error::Pattern { message, .. }) = file_handler::glob(pattern) {
+                diagnostics.push(Diagnostic {
+                    severity: Severity::Error,
+                    location: locate_language(documents, name, "compile_inputs"),
+                    message: format!(
+                        "invalid pattern in `compile_inputs` of `{name}`: {}",
+                        pattern.display()
+                    ),
+                    help: Some(message),
+                });
+            }
+        }
+
+        let commands = [
+            ("compile", language.compile.as_ref()),
+            ("execute", Some(&language.execute)),
+            ("version", language.version.as_ref()),
+        ];
+        for (key, command) in commands {
+            if let Some(command) = command {
+                let location = || locate_language(documents, name, key);
+                check_command(command, &format!("{name}.{key}"), location, diagnostics);
+            }
+        }
+    }
+
+    if let Some(command) = &user_config.login.password_command {
+        check_command(command, "login.password_command", || None, diagnostics);
+    }
+}
+
+fn check_command(
+    Command {
+        command,
+        working_dir,
+        shell,
+        ..
+    }: &Command,
+    context: &str,
+    location: impl Fn() -> Option<Location>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    if let Some(working_dir) = working_dir
+        .as_ref()
+        .filter(|dir| !is_template(dir) && !dir.is_dir())
+    {
+        diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            location: location(),
+            message: format!(
+                "`working_dir` of `{context}` does not exist: {}",
+                working_dir.display()
+            ),
+            help: None,
+        });
+    }
+
+    // Paths such as `./a.out` may be created by the compile command
+    let is_path = command.contains(['/', '\\']);
+    if !is_path
+        && !shell
+        && !variables::has_variables(command)
+        && command_handler::find_in_path(command).is_none()
+    {
+        diagnostics.push(Diagnostic {
+            severity: Severity::Warning,
+            location: location(),
+            message: format!("`{command}` of `{context}` is not found on $PATH"),
+            help: None,
+        });
+    }
+}
+
+fn is_template(path: &Path) -> bool {
+    variables::has_variables(&path.to_string_lossy())
+}
+
+/// Location of a top-level key in the file of the highest precedence defining it
+fn locate_key(documents: &[Document], key: &str) -> Option<Location> {
+    documents.iter().rev().find_map(|document| {
+        let (key, _) = document.document.as_table().get_key_value(key)?;
+        document.location(key.span())
+    })
+}
+
+/// Location of a key of a language in the file of the highest precedence defining it
+fn locate_language(documents: &[Document], name: &str, key: &str) -> Option<Location> {
+    documents.iter().rev().find_map(|document| {
+        let language = tables(document.document.get("language")?)
+            .into_iter()
+            .find(|language| language.get("name").and_then(Item::as_str) == Some(name))?;
+        let (key, _) = language
+            .get_key_value(key)
+            .or_else(|| language.get_key_value("name"))?;
+        document.location(key.span())
+    })
+}
+
+fn location(file: &Path, text: &str, offset: usize) -> Option<Location> {
+    let before = text.get(..offset)?;
+    Some(Location {
+        file: file.to_path_buf(),
+        line: before.matches('\n').count() + 1,
+        column: before
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .count()
+            + 1,
+    })
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    FileHandler(#[from] file_handler::Error),
+
+    #[error("Invalid Config: {}", .0)]
+    Invalid(#[from] toml::de::Error),
+
+    #[error("{} Not Found in the current or any parent directory. Run `init` to create it", .0.display())]
+    NotInitialized(PathBuf),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dto::config::StoreKind, utils::test::temp_dir};
+    use std::fs;
+
+    #[test]
+    fn test_merge_languages() {
+        // Setup
+        let mut base: Table = r#"
+            [[language]]
+            name = "rust"
+            id = "5054"
+            src_path = "src/main.rs"
+            execute = { command = "cargo", args = ["run"] }
+
+            [[language]]
+            name = "python"
+            id = "5055"
+            src_path = "main.py"
+            execute = { command = "python3", args = ["main.py"] }
+        "#
+        .parse()
+        .unwrap();
+        let overlay: Table = r#"
+            [[language]]
+            name = "python"
+            execute = { command = "pypy3", args = ["main.py"] }
+        "#
+        .parse()
+        .unwrap();
+
+        // Run
+        merge(&mut base, overlay);
+
+        // Verify
+        let languages = base["language"].as_array().unwrap();
+        assert_eq!(2, languages.len());
+        assert_eq!("5055", languages[1]["id"].as_str().unwrap());
+        assert_eq!(
+            "pypy3",
+            languages[1]["execute"]["command"].as_str().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_user_config_template() {
+        // Run
+        let user_config: Result<UserConfig, _> = toml::from_str(USER_CONFIG_TEMPLATE);
+
+        // Verify
+        let user_config = user_config.unwrap();
+        for language in ["rust", "cpp", "cpp-clang", "python", "pypy", "java"] {
+            assert!(user_config.language_config(language).is_some());
+        }
+    }
+
+    #[test]
+    fn test_check_user_config() {
+        // Setup
+        let file = temp_dir("check_user_config").join("config.toml");
+        fs::write(
+            &file,
+            [
+                "[http]",
+                "retries = 1",
+                "",
+                "[[language]]",
+                "name = \"rust\"",
+                "id = \"5054\"",
+                &format!("src_path = '{}'", file.display()),
+                "exeucte = { command = \"cargo\", args = [\"run\"] }",
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+
+        // Run
+        let diagnostics = check_user_config(std::slice::from_ref(&file)).unwrap();
+
+        // Verify
+        assert_eq!(2, diagnostics.len());
+        assert_eq!(
+            "unknown key `exeucte` in `language`",
+            diagnostics[0].message
+        );
+        assert_eq!(
+            Some("did you mean `execute`?".to_string()),
+            diagnostics[0].help
+        );
+        assert_eq!(
+            Some(Location {
+                file: file.clone(),
+                line: 8,
+                column: 1
+            }),
+            diagnostics[0].location
+        );
+        assert_eq!("language `rust` has no `execute`", diagnostics[1].message);
+        assert_eq!(
+            Some(5),
+            diagnostics[1]
+                .location
+                .as_ref()
+                .map(|location| location.line)
+        );
+    }
+
+    #[test]
+    fn test_check_syntax_error() {
+        // Setup
+        let file = temp_dir("check_syntax_error").join("syntax_error.toml");
+        fs::write(&file, "[http]\nretries = \n").unwrap();
+
+        // Run
+        let diagnostics = check_user_config(&[file]).unwrap();
+
+        // Verify
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(Severity::Error, diagnostics[0].severity);
+        assert_eq!(
+            Some(2),
+            diagnostics[0]
+                .location
+                .as_ref()
+                .map(|location| location.line)
+        );
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        // Setup
+        let vars = [
+            ("ATCODER_TOOLS_HTTP__RETRIES", "5"),
+            ("ATCODER_TOOLS_SESSION__STORE", "keyring"),
+            ("ATCODER_TOOLS_DEFAULT_LANGUAGE", "python"),
+            ("ATCODER_TOOLS_SESSION__PROFILE", "1234"),
+            ("ATCODER_TOOLS_PASSPHRASE", "secret"),
+            ("HOME", "/home/user"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+
+        // Run
+        let overrides = env_overrides(vars);
+
+        // Verify
+        assert_eq!(Some(5), overrides["http"]["retries"].as_integer());
+        assert_eq!(Some("keyring"), overrides["session"]["store"].as_str());
+        assert_eq!(Some("1234"), overrides["session"]["profile"].as_str());
+        assert_eq!(Some("python"), overrides["default_language"].as_str());
+        assert_eq!(3, overrides.len());
+    }
+
+    #[test]
+    fn test_load_user_config() {
+        // Setup
+        let dir = temp_dir("load_user_config");
+        let global = dir.join("global.toml");
+        let project = dir.join("project.toml");
+        fs::write(&global, "[http]\nretries = 1\ncache = false\n").unwrap();
+        fs::write(&project, "[http]\nretries = 2\n").unwrap();
+        let vars = [
+            ("ATCODER_TOOLS_SESSION__STORE", "encrypted"),
+            ("ATCODER_TOOLS_DEFAULT_LANGUAGE", "true"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+
+        // Run
+        let user_config =
+            load_user_config(&[global, project, dir.join("missing.toml")], vars).unwrap();
+
+        // Verify
+        assert_eq!(2, user_config.http.retries);
+        assert!(!user_config.http.cache);
+        assert!(matches!(user_config.session.store, StoreKind::Encrypted));
+        assert_eq!(Some("true"), user_config.default_language.as_deref());
+    }
+}
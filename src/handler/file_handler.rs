@@ -1,16 +1,15 @@
 use crate::{
     domain::path::TaskTestPath,
-    dto::{
-        config::{AppConfig, Config},
-        TestCase, TestCaseFile, TestCases, TestSuite,
-    },
+    dto::{cookie, TestCase, TestCaseFile, TestCases, TestSuite},
 };
+use cookie_store::Cookie;
 use serde::{de::DeserializeOwned, Serialize};
 use std::{
     env,
     ffi::OsString,
     fs,
     path::{Path, PathBuf},
+    time::SystemTime,
 };
 
 pub fn save_test_suite(test_dir: &Path, test_suite: &TestSuite) -> Result<(), Error> {
@@ -90,33 +89,124 @@ where
     Ok(data)
 }
 
-pub fn load_config() -> Result<Config, Error> {
-    // Load config for app
-    let app_config: AppConfig = toml::from_str(include_str!(concat!(
-        env!("CARGO_MANIFEST_DIR"),
-        "/config.toml"
-    )))
-    .with_path(Path::new(concat!(
-        env!("CARGO_MANIFEST_DIR"),
-        "/config.toml"
-    )))?;
+pub fn save_text(file_path: &Path, contents: &str) -> Result<(), Error> {
+    if let Some(dir) = file_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).with_path(dir)?;
+    }
+    fs::write(file_path, contents).with_path(file_path)?;
+    Ok(())
+}
+
+/// Appends a line unless the file already has it, and returns whether it was appended
+pub fn append_line(file_path: &Path, line: &str) -> Result<bool, Error> {
+    let contents = match fs::read_to_string(file_path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(error) => return Err(error).with_path(file_path),
+    };
+
+    if contents.lines().any(|existing| existing.trim() == line) {
+        return Ok(false);
+    }
+
+    let separator = if contents.is_empty() || contents.ends_with('\n') {
+        ""
+    } else {
+        "\n"
+    };
+    fs::write(file_path, format!("{contents}{separator}{line}\n")).with_path(file_path)?;
+    Ok(true)
+}
+
+pub fn load_cookie_export(file_path: &Path) -> Result<Vec<Cookie<'static>>, Error> {
+    let text = fs::read_to_string(file_path).with_path(file_path)?;
+    let cookies = cookie::parse_cookie_export(&text).with_path(file_path)?;
+    Ok(cookies)
+}
+
+/// `$XDG_DATA_HOME`, falling back to `~/.local/share`
+pub fn data_home() -> Option<PathBuf> {
+    base_dir(
+        env::var_os("XDG_DATA_HOME"),
+        env::var_os("HOME"),
+        ".local/share",
+    )
+}
+
+/// `$XDG_CONFIG_HOME`, falling back to `~/.config`
+pub fn config_home() -> Option<PathBuf> {
+    base_dir(
+        env::var_os("XDG_CONFIG_HOME"),
+        env::var_os("HOME"),
+        ".config",
+    )
+}
 
-    // Move to the root directory for app
-    let metadata_path = find_in_ancestors(&app_config.path.metadata)
-        .unwrap_or_else(|| panic!("Failed to {:?} in ancestors", app_config.path.metadata));
-    let root_path = metadata_path.parent().unwrap();
-    env::set_current_dir(root_path).with_path(root_path)?;
+/// XDG base directory from its variable, falling back to the default under the home directory
+fn base_dir(dir: Option<OsString>, home: Option<OsString>, default: &str) -> Option<PathBuf> {
+    dir.filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home.map(|home| Path::new(&home).join(default)))
+}
 
-    // Load config for user
-    let user_config = load_toml(&app_config.path.user_config)?;
+/// Contents of a source file, or of every file under a source directory in order of path
+pub fn load_sources(path: &Path) -> Result<Vec<(PathBuf, Vec<u8>)>, Error> {
+    if !path.is_dir() {
+        let contents = fs::read(path).with_path(path)?;
+        return Ok(vec![(path.to_path_buf(), contents)]);
+    }
 
-    Ok(Config {
-        app_config,
-        user_config,
-    })
+    let mut entries = fs::read_dir(path)
+        .with_path(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .with_path(path)?;
+    entries.sort();
+
+    let mut sources = Vec::new();
+    for entry in entries {
+        sources.extend(load_sources(&entry)?);
+    }
+    Ok(sources)
 }
 
-fn find_in_ancestors(target: &Path) -> Option<PathBuf> {
+/// Files matching a pattern in order of path, where `*` and `?` match within a file name and `**`
+/// matches any number of directories, e.g. `src/**/*.rs`
+pub fn glob(pattern: &Path) -> Result<Vec<PathBuf>, Error> {
+    let options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    let pattern_str = pattern
+        .to_str()
+        .ok_or_else(|| Error::OsString(pattern.as_os_str().to_owned()))?;
+    let paths = glob::glob_with(pattern_str, options).map_err(|error| Error::Pattern {
+        message: error.to_string(),
+        path: pattern.to_path_buf(),
+    })?;
+
+    let mut files = Vec::new();
+    for path in paths {
+        let path = path.map_err(|error| Error::IO {
+            path: error.path().to_path_buf(),
+            source: error.into(),
+        })?;
+        if path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Last modification time, or `None` if the file does not exist
+pub fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+pub fn find_in_ancestors(target: &Path) -> Option<PathBuf> {
     let current_dir = env::current_dir().expect("Failed to get current directory");
 
     current_dir
@@ -137,6 +227,9 @@ pub enum Error {
     #[error("{message}: {path}")]
     Serde { message: String, path: PathBuf },
 
+    #[error("Invalid pattern: {message}: {path}")]
+    Pattern { message: String, path: PathBuf },
+
     #[error("Failed to convert OsString to String: {:?}", .0)]
     OsString(OsString),
 }
@@ -163,6 +256,15 @@ impl<T> WithPath<T, Error> for Result<T, serde_json::Error> {
     }
 }
 
+impl<T> WithPath<T, Error> for Result<T, cookie::Error> {
+    fn with_path(self, path: impl AsRef<Path>) -> Result<T, Error> {
+        self.map_err(|source| Error::Serde {
+            message: source.to_string(),
+            path: path.as_ref().to_path_buf(),
+        })
+    }
+}
+
 impl<T> WithPath<T, Error> for Result<T, toml::de::Error> {
     fn with_path(self, path: impl AsRef<Path>) -> Result<T, Error> {
         self.map_err(|source| Error::Serde {
@@ -174,7 +276,7 @@ impl<T> WithPath<T, Error> for Result<T, toml::de::Error> {
 
 #[cfg(test)]
 mod tests {
-    use crate::dto::TaskInfo;
+    use crate::{dto::TaskInfo, utils::test::temp_dir};
 
     use super::*;
 
@@ -205,12 +307,67 @@ mod tests {
         ];
 
         // Run
-        let result = save_test_suite(Path::new("tests/data/test"), &test_suite);
+        let result = save_test_suite(&temp_dir("save_test_suite"), &test_suite);
 
         // Verify
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_base_dir() {
+        // Setup
+        let home = || Some(OsString::from("/home/user"));
+
+        // Run
+        let set = base_dir(Some("/tmp/xdg-data".into()), home(), ".local/share");
+        let empty = base_dir(Some("".into()), home(), ".local/share");
+        let unset = base_dir(None, home(), ".local/share");
+        let no_home = base_dir(None, None, ".local/share");
+
+        // Verify
+        assert_eq!(Some(PathBuf::from("/tmp/xdg-data")), set);
+        assert_eq!(Some(PathBuf::from("/home/user/.local/share")), empty);
+        assert_eq!(Some(PathBuf::from("/home/user/.local/share")), unset);
+        assert_eq!(None, no_home);
+    }
+
+    #[test]
+    fn test_glob() {
+        // Setup
+        let dir = temp_dir("glob");
+        fs::create_dir_all(dir.join("src/nested")).unwrap();
+        for file in [
+            "Cargo.toml",
+            "src/main.rs",
+            "src/lib.hpp",
+            "src/nested/mod.rs",
+        ] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+
+        // Run
+        let literal = glob(&dir.join("Cargo.toml")).unwrap();
+        let wildcard = glob(&dir.join("src/*.?pp")).unwrap();
+        let class = glob(&dir.join("src/[a-m]*")).unwrap();
+        let recursive = glob(&dir.join("**/*.rs")).unwrap();
+        let missing = glob(&dir.join("missing.toml")).unwrap();
+        let invalid = glob(&dir.join("src/[.rs"));
+
+        // Verify
+        assert_eq!(vec![dir.join("Cargo.toml")], literal);
+        assert_eq!(vec![dir.join("src/lib.hpp")], wildcard);
+        assert_eq!(
+            vec![dir.join("src/lib.hpp"), dir.join("src/main.rs")],
+            class
+        );
+        assert_eq!(
+            vec![dir.join("src/main.rs"), dir.join("src/nested/mod.rs")],
+            recursive
+        );
+        assert!(missing.is_empty());
+        assert!(matches!(invalid, Err(Error::Pattern { .. })));
+    }
+
     #[test]
     fn test_save_data() {
         // Setup
@@ -221,7 +378,7 @@ mod tests {
         }];
 
         // Run
-        let result = save(Path::new("tests/data/tasks_info.json"), &tasks_info);
+        let result = save(&temp_dir("save_data").join("tasks_info.json"), &tasks_info);
 
         // Verify
         assert!(result.is_ok());
@@ -1,29 +1,65 @@
+use super::{
+    cache_handler::ResponseCache,
+    file_handler,
+    transport::{RecordingTransport, ReplayTransport, Response, Transport, UreqTransport},
+};
 use crate::{
     domain::{html::Html, url::Url},
-    dto::cookie::IntoCookieStore,
+    dto::{config::HttpConfig, CacheEntry},
 };
 use cookie_store::Cookie;
-use ureq::Agent;
+use std::{
+    cell::Cell,
+    io, thread,
+    time::{Duration, Instant},
+};
+use ureq::ErrorKind;
 
 pub struct HttpHandler {
-    agent: Agent,
+    transport: Box<dyn Transport>,
+    config: HttpConfig,
+    last_request: Cell<Option<Instant>>,
+    cache: Option<ResponseCache>,
 }
 
 impl HttpHandler {
-    pub fn new(agent: Agent) -> Self {
-        Self { agent }
+    pub fn new(config: &HttpConfig) -> Result<Self, Error> {
+        Self::with_cookies(Vec::new(), config)
+    }
+
+    pub fn with_cookies(cookies: Vec<Cookie<'static>>, config: &HttpConfig) -> Result<Self, Error> {
+        let transport: Box<dyn Transport> = match (&config.replay, &config.record) {
+            (Some(file), _) => Box::new(ReplayTransport::load(file)?),
+            (None, Some(file)) => Box::new(RecordingTransport::new(
+                UreqTransport::new(config, cookies),
+                file,
+            )),
+            (None, None) => Box::new(UreqTransport::new(config, cookies)),
+        };
+
+        Ok(Self::with_transport(transport, config))
     }
 
-    pub fn with_cookies(cookies: Vec<Cookie<'static>>) -> Self {
-        let cookie_store = cookies.into_cookie_store();
-        let agent = ureq::builder().cookie_store(cookie_store).build();
-        Self { agent }
+    pub fn with_transport(transport: Box<dyn Transport>, config: &HttpConfig) -> Self {
+        Self {
+            transport,
+            config: config.clone(),
+            last_request: Cell::new(None),
+            cache: None,
+        }
+    }
+
+    pub fn with_cache(self, cache: ResponseCache) -> Self {
+        Self {
+            cache: self.config.cache.then_some(cache),
+            ..self
+        }
     }
 
     pub fn get<PageType>(&self, url: &Url<PageType>) -> Result<Html<PageType>, Error> {
-        let response = self.agent.get(url).call()?;
-        let html = response.into_string()?.replace("\r", "");
-        Ok(html.into())
+        self.check_online(url)?;
+        let response = self.call_with_retry(|| self.transport.get(url, &[]))?;
+        Ok(normalize(response.body).into())
     }
 
     pub fn post<'a, RequestPageType, ResponsePageType>(
@@ -31,39 +67,213 @@ impl HttpHandler {
         url: &Url<RequestPageType>,
         data: impl Into<Vec<(&'static str, &'a str)>>,
     ) -> Result<Html<ResponsePageType>, Error> {
-        let response = self.agent.post(url).send_form(&data.into())?;
-        let html = response.into_string()?.replace("\r", "").into();
+        // POST is not idempotent, so it is never retried
+        self.check_online(url)?;
+        self.wait_for_interval();
+        let response = check_status(self.transport.post_form(url, &data.into())?)?;
+        Ok(normalize(response.body).into())
+    }
+
+    /// GET which is served from the cache when offline, and revalidated with ETag or Last-Modified otherwise
+    pub fn get_cached<PageType>(&self, url: &Url<PageType>) -> Result<Html<PageType>, Error> {
+        let Some(cache) = &self.cache else {
+            return self.get(url);
+        };
+
+        let cached = cache.load(url);
+
+        if self.config.offline {
+            return cached
+                .map(|entry| entry.body.into())
+                .ok_or_else(|| Error::NotCached {
+                    url: url.to_string(),
+                });
+        }
+
+        let mut headers = Vec::new();
+        if let Some(entry) = &cached {
+            if let Some(etag) = &entry.etag {
+                headers.push(("If-None-Match", etag.as_str()));
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                headers.push(("If-Modified-Since", last_modified.as_str()));
+            }
+        }
+
+        let response = self.call_with_retry(|| self.transport.get(url, &headers))?;
+
+        if let (304, Some(entry)) = (response.status, &cached) {
+            return Ok(entry.body.clone().into());
+        }
+
+        let entry = CacheEntry {
+            url: url.to_string(),
+            etag: response.header("ETag").map(Into::into),
+            last_modified: response.header("Last-Modified").map(Into::into),
+            body: normalize(response.body),
+        };
+        let html = Html::from(entry.body.clone());
+        // Not to replay the sign-in page shown in place of the page to an expired session
+        if !html.is_sign_in_page() {
+            cache.save(&entry)?;
+        }
+
         Ok(html)
     }
 
     pub fn into_cookies(self) -> Vec<Cookie<'static>> {
-        self.agent
-            .cookie_store()
-            .iter_unexpired()
-            .cloned()
-            .collect()
+        self.transport.cookies()
+    }
+
+    fn call_with_retry(
+        &self,
+        call: impl Fn() -> Result<Response, Error>,
+    ) -> Result<Response, Error> {
+        let mut attempt = 0;
+        loop {
+            self.wait_for_interval();
+
+            match call().and_then(check_status) {
+                Err(error) if error.is_retryable() && attempt < self.config.retries => {
+                    thread::sleep(retry_delay(&self.config, attempt));
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn check_online(&self, url: &str) -> Result<(), Error> {
+        if self.config.offline {
+            Err(Error::Offline {
+                url: url.to_string(),
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Sleeps so that requests are at least `min_interval_ms` apart
+    fn wait_for_interval(&self) {
+        let min_interval = Duration::from_millis(self.config.min_interval_ms);
+
+        if let Some(last_request) = self.last_request.get() {
+            let elapsed = last_request.elapsed();
+            if elapsed < min_interval {
+                thread::sleep(min_interval - elapsed);
+            }
+        }
+
+        self.last_request.set(Some(Instant::now()));
+    }
+}
+
+fn check_status(response: Response) -> Result<Response, Error> {
+    if response.status >= 400 {
+        Err(Error::Status {
+            code: response.status,
+            url: response.url,
+        })
+    } else {
+        Ok(response)
     }
 }
 
+fn normalize(body: String) -> String {
+    body.replace("\r", "")
+}
+
+fn retry_delay(config: &HttpConfig, attempt: u32) -> Duration {
+    Duration::from_millis(config.backoff_ms.saturating_mul(1 << attempt.min(16)))
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
+    #[error("HTTP Status {code}: {url}")]
+    Status { code: u16, url: String },
+
+    #[error("Timed Out: {url}")]
+    Timeout { url: String },
+
+    #[error("DNS Lookup Failed: {url}")]
+    Dns { url: String },
+
     #[error("HTTP Error: {:?}", .0)]
-    HttpError(#[source] Box<ureq::Error>),
+    Transport(#[source] Box<ureq::Error>),
 
     #[error("Too Large Response")]
     TooLargeResponse(#[from] std::io::Error),
+
+    #[error("Network access is disabled in offline mode: {url}")]
+    Offline { url: String },
+
+    #[error("Not cached, unavailable in offline mode: {url}")]
+    NotCached { url: String },
+
+    #[error(transparent)]
+    File(#[from] file_handler::Error),
+
+    #[error("No Recorded Response: {method} {url}")]
+    NotRecorded { method: String, url: String },
+}
+
+impl Error {
+    fn is_retryable(&self) -> bool {
+        match self {
+            Error::Status { code, .. } => *code == 429 || *code >= 500,
+            Error::Timeout { .. } => true,
+            Error::Transport(error) => error.kind() == ErrorKind::ConnectionFailed,
+            Error::Dns { .. }
+            | Error::TooLargeResponse(_)
+            | Error::Offline { .. }
+            | Error::NotCached { .. }
+            | Error::File(_)
+            | Error::NotRecorded { .. } => false,
+        }
+    }
 }
 
 impl From<ureq::Error> for Error {
     fn from(value: ureq::Error) -> Self {
-        Error::HttpError(Box::new(value))
+        match value {
+            ureq::Error::Status(code, response) => Error::Status {
+                code,
+                url: response.get_url().to_string(),
+            },
+            ureq::Error::Transport(transport) => {
+                let url = transport
+                    .url()
+                    .map_or_else(String::new, ToString::to_string);
+
+                match transport.kind() {
+                    ErrorKind::Dns => Error::Dns { url },
+                    ErrorKind::Io if is_timeout(&transport) => Error::Timeout { url },
+                    _ => Error::Transport(Box::new(ureq::Error::Transport(transport))),
+                }
+            }
+        }
     }
 }
 
+fn is_timeout(transport: &ureq::Transport) -> bool {
+    std::error::Error::source(transport)
+        .and_then(|source| source.downcast_ref::<io::Error>())
+        .is_some_and(|error| {
+            matches!(
+                error.kind(),
+                io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+            )
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{domain::page_type, utils};
+    use crate::{
+        domain::page_type,
+        utils,
+        utils::test::{temp_dir, FixtureTransport},
+    };
 
     #[test]
     #[ignore]
@@ -72,9 +282,7 @@ mod tests {
         let expected = utils::test::load_homepage_html().html();
         let expected = expected.split('\n').collect::<Vec<_>>();
 
-        let http_handler = HttpHandler {
-            agent: Agent::new(),
-        };
+        let http_handler = HttpHandler::new(&HttpConfig::default()).unwrap();
 
         // Run
         let url: Url<page_type::Home> = "https://atcoder.jp/home".to_string().into();
@@ -89,4 +297,143 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_retry_delay() {
+        // Setup
+        let config = HttpConfig {
+            backoff_ms: 500,
+            ..Default::default()
+        };
+
+        // Run
+        let delays = (0..4)
+            .map(|attempt| retry_delay(&config, attempt).as_millis())
+            .collect::<Vec<_>>();
+
+        // Verify
+        assert_eq!(vec![500, 1000, 2000, 4000], delays);
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        let url = "https://atcoder.jp".to_string();
+
+        assert!(Error::Status {
+            code: 503,
+            url: url.clone()
+        }
+        .is_retryable());
+        assert!(Error::Status {
+            code: 429,
+            url: url.clone()
+        }
+        .is_retryable());
+        assert!(!Error::Status {
+            code: 404,
+            url: url.clone()
+        }
+        .is_retryable());
+        assert!(Error::Timeout { url: url.clone() }.is_retryable());
+        assert!(!Error::Dns { url }.is_retryable());
+    }
+
+    #[test]
+    fn test_get_cached_offline() {
+        // Setup
+        let config = HttpConfig {
+            offline: true,
+            ..Default::default()
+        };
+        let cache = ResponseCache::new(temp_dir("get_cached_offline"));
+        cache
+            .save(&CacheEntry {
+                url: "https://atcoder.jp/contests/offline".to_string(),
+                etag: None,
+                last_modified: None,
+                body: "<title>Offline</title>".to_string(),
+            })
+            .unwrap();
+        let http_handler = HttpHandler::new(&config).unwrap().with_cache(cache);
+
+        // Run
+        let cached: Result<Html<page_type::ContestHome>, _> =
+            http_handler.get_cached(&"https://atcoder.jp/contests/offline".to_string().into());
+        let not_cached: Result<Html<page_type::ContestHome>, _> =
+            http_handler.get_cached(&"https://atcoder.jp/contests/missing".to_string().into());
+
+        // Verify
+        assert!(cached.is_ok());
+        assert!(matches!(not_cached, Err(Error::NotCached { .. })));
+    }
+
+    #[test]
+    fn test_get_cached_sign_in_page() {
+        // Setup
+        let url = "https://atcoder.jp/contests/abc388/tasks/abc388_a";
+        let transport = FixtureTransport::new().route_get(url, "login_page.html");
+        let config = HttpConfig {
+            min_interval_ms: 0,
+            ..Default::default()
+        };
+        let dir = temp_dir("get_cached_sign_in_page");
+        let cache = ResponseCache::new(&dir);
+        let http_handler = HttpHandler::with_transport(Box::new(transport), &config)
+            .with_cache(ResponseCache::new(&dir));
+
+        // Run
+        let html: Html<page_type::Task> = http_handler.get_cached(&url.to_string().into()).unwrap();
+
+        // Verify
+        assert!(html.is_sign_in_page());
+        assert!(cache.load(url).is_none());
+    }
+
+    #[test]
+    fn test_get_cached_anonymous() {
+        // Setup
+        let url = "https://atcoder.jp/contests/abc388/tasks/abc388_a";
+        let transport = FixtureTransport::new().route_get(url, "task_page_logged_out.html");
+        let config = HttpConfig {
+            min_interval_ms: 0,
+            ..Default::default()
+        };
+        let dir = temp_dir("get_cached_anonymous");
+        let http_handler = HttpHandler::with_transport(Box::new(transport), &config)
+            .with_cache(ResponseCache::new(&dir));
+        let offline_config = HttpConfig {
+            offline: true,
+            ..Default::default()
+        };
+        let offline_handler = HttpHandler::new(&offline_config)
+            .unwrap()
+            .with_cache(ResponseCache::new(&dir));
+
+        // Run
+        let html: Html<page_type::Task> = http_handler.get_cached(&url.to_string().into()).unwrap();
+        let offline: Result<Html<page_type::Task>, _> =
+            offline_handler.get_cached(&url.to_string().into());
+
+        // Verify
+        assert!(html.is_logged_out());
+        assert!(offline.unwrap().is_logged_out());
+    }
+
+    #[test]
+    fn test_wait_for_interval() {
+        // Setup
+        let config = HttpConfig {
+            min_interval_ms: 50,
+            ..Default::default()
+        };
+        let http_handler = HttpHandler::new(&config).unwrap();
+
+        // Run
+        let start = Instant::now();
+        http_handler.wait_for_interval();
+        http_handler.wait_for_interval();
+
+        // Verify
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
 }
@@ -1,4 +1,8 @@
+pub mod cache_handler;
 pub mod command_handler;
+pub mod config_handler;
 pub mod file_handler;
 pub mod http_handler;
+pub mod session_handler;
 pub mod terminal_handler;
+pub mod transport;
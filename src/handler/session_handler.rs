@@ -0,0 +1,430 @@
+use super::{
+    command_handler,
+    file_handler::{self, WithPath},
+    terminal_handler,
+};
+use crate::dto::{
+    config::{SessionConfig, StoreKind},
+    Command, SessionData,
+};
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{ExitStatus, Output},
+    sync::OnceLock,
+};
+
+pub const PASSPHRASE_ENV: &str = "ATCODER_TOOLS_PASSPHRASE";
+const KEYRING_SERVICE: &str = "atcoder-tools";
+
+/// Passphrase entered once per process, so that loading and saving prompt only once
+static PASSPHRASE: OnceLock<String> = OnceLock::new();
+
+pub trait SessionStore {
+    fn load(&self) -> Result<SessionData, Error>;
+    fn save(&self, session_data: &SessionData) -> Result<(), Error>;
+    fn remove(&self) -> Result<(), Error>;
+
+    /// Human readable location of the stored session
+    fn location(&self) -> String;
+}
+
+pub fn session_store(config: &SessionConfig, path: &Path, profile: &str) -> Box<dyn SessionStore> {
+    match config.store {
+        StoreKind::File => Box::new(FileStore::new(path)),
+        StoreKind::Encrypted => Box::new(EncryptedFileStore::new(path)),
+        StoreKind::Keyring => Box::new(KeyringStore::new(profile)),
+    }
+}
+
+/// Plaintext JSON file readable only by the owner
+pub struct FileStore {
+    path: PathBuf,
+}
+
+impl FileStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl SessionStore for FileStore {
+    fn load(&self) -> Result<SessionData, Error> {
+        Ok(file_handler::load(&self.path)?)
+    }
+
+    fn save(&self, session_data: &SessionData) -> Result<(), Error> {
+        create_parent_dir(&self.path)?;
+        save_private(&self.path, session_data)?;
+        warn_if_tracked(&self.path);
+        Ok(())
+    }
+
+    fn remove(&self) -> Result<(), Error> {
+        remove_file(&self.path)
+    }
+
+    fn location(&self) -> String {
+        self.path.display().to_string()
+    }
+}
+
+/// JSON file encrypted with a key derived from a passphrase by Argon2
+pub struct EncryptedFileStore {
+    path: PathBuf,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EncryptedData {
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl EncryptedFileStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl SessionStore for EncryptedFileStore {
+    fn load(&self) -> Result<SessionData, Error> {
+        let data: EncryptedData = file_handler::load(&self.path)?;
+        let passphrase = read_passphrase()?;
+        decrypt(&data, &passphrase)
+    }
+
+    fn save(&self, session_data: &SessionData) -> Result<(), Error> {
+        let passphrase = read_passphrase()?;
+        create_parent_dir(&self.path)?;
+        save_private(&self.path, &encrypt(session_data, &passphrase)?)?;
+        warn_if_tracked(&self.path);
+        Ok(())
+    }
+
+    fn remove(&self) -> Result<(), Error> {
+        remove_file(&self.path)
+    }
+
+    fn location(&self) -> String {
+        format!("{} (encrypted)", self.path.display())
+    }
+}
+
+fn read_passphrase() -> Result<String, Error> {
+    if let Some(passphrase) = PASSPHRASE.get() {
+        return Ok(passphrase.clone());
+    }
+
+    let passphrase = match env::var(PASSPHRASE_ENV) {
+        Ok(passphrase) => passphrase,
+        Err(_) => terminal_handler::read_passphrase().map_err(Error::Terminal)?,
+    };
+
+    Ok(PASSPHRASE.get_or_init(|| passphrase).clone())
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key, Error> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|error| Error::Crypto(error.to_string()))?;
+    Ok(key)
+}
+
+fn encrypt(session_data: &SessionData, passphrase: &str) -> Result<EncryptedData, Error> {
+    let mut salt = [0; 16];
+    OsRng.fill_bytes(&mut salt);
+
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let plaintext = serde_json::to_vec(session_data)?;
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_slice())
+        .map_err(|error| Error::Crypto(error.to_string()))?;
+
+    Ok(EncryptedData {
+        salt: BASE64.encode(salt),
+        nonce: BASE64.encode(nonce),
+        ciphertext: BASE64.encode(ciphertext),
+    })
+}
+
+fn decrypt(data: &EncryptedData, passphrase: &str) -> Result<SessionData, Error> {
+    let decode = |text: &str| {
+        BASE64
+            .decode(text)
+            .map_err(|error| Error::Crypto(error.to_string()))
+    };
+    let salt = decode(&data.salt)?;
+    let nonce = decode(&data.nonce)?;
+    let ciphertext = decode(&data.ciphertext)?;
+
+    if nonce.len() != 12 {
+        return Err(Error::Crypto("Invalid Nonce".to_string()));
+    }
+
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| Error::WrongPassphrase)?;
+
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+/// Secret Service keyring accessed through `secret-tool`
+pub struct KeyringStore {
+    account: String,
+}
+
+impl KeyringStore {
+    pub fn new(account: impl Into<String>) -> Self {
+        Self {
+            account: account.into(),
+        }
+    }
+
+    fn secret_tool(&self, action: &str, extra_args: &[&str]) -> Command {
+        let args = [action]
+            .iter()
+            .chain(extra_args)
+            .chain(&["service", KEYRING_SERVICE, "account", &self.account])
+            .map(ToString::to_string)
+            .collect();
+
+        Command {
+            command: "secret-tool".to_string(),
+            args,
+            ..Default::default()
+        }
+    }
+}
+
+impl SessionStore for KeyringStore {
+    fn load(&self) -> Result<SessionData, Error> {
+        let output: Output =
+            command_handler::run(&self.secret_tool("lookup", &[]), None).map_err(Error::Keyring)?;
+
+        if !output.status.success() || output.stdout.is_empty() {
+            return Err(Error::NotFound(self.location()));
+        }
+
+        Ok(serde_json::from_slice(&output.stdout)?)
+    }
+
+    fn save(&self, session_data: &SessionData) -> Result<(), Error> {
+        let label = format!("--label=AtCoder session ({})", self.account);
+        let command = self.secret_tool("store", &[&label]);
+        let secret = serde_json::to_string(session_data)?;
+
+        let output: Output =
+            command_handler::run(&command, Some(&secret)).map_err(Error::Keyring)?;
+        check_keyring_status(output.status, &output.stderr)
+    }
+
+    fn remove(&self) -> Result<(), Error> {
+        let output: Output =
+            command_handler::run(&self.secret_tool("clear", &[]), None).map_err(Error::Keyring)?;
+        check_keyring_status(output.status, &output.stderr)
+    }
+
+    fn location(&self) -> String {
+        format!(
+            "keyring (service: {KEYRING_SERVICE}, account: {})",
+            self.account
+        )
+    }
+}
+
+fn check_keyring_status(status: ExitStatus, stderr: &[u8]) -> Result<(), Error> {
+    if status.success() {
+        Ok(())
+    } else {
+        let message = String::from_utf8_lossy(stderr).trim().to_string();
+        Err(Error::Keyring(std::io::Error::other(message)))
+    }
+}
+
+fn create_parent_dir(path: &Path) -> Result<(), Error> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).with_path(dir)?;
+    }
+    Ok(())
+}
+
+fn remove_file(path: &Path) -> Result<(), Error> {
+    if !path.exists() {
+        return Err(Error::NotFound(path.display().to_string()));
+    }
+    fs::remove_file(path).with_path(path)?;
+    Ok(())
+}
+
+/// Saves as JSON readable only by the owner, never exposing the contents to other users
+fn save_private<T: Serialize>(path: &Path, data: &T) -> Result<(), Error> {
+    let contents = serde_json::to_string_pretty(data).with_path(path)?;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        // Applied only when the file is created
+        options.mode(0o600);
+    }
+
+    if path.exists() {
+        restrict_permissions(path)?;
+    }
+    let mut file = options.open(path).with_path(path)?;
+    file.write_all(contents.as_bytes()).with_path(path)?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn restrict_permissions(path: &Path) -> Result<(), Error> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600)).with_path(path)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_: &Path) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Warns if the session file is tracked by git, where it is easily committed by accident
+fn warn_if_tracked(path: &Path) {
+    if is_tracked_by_git(path) {
+        eprintln!(
+            "Warning: {} is tracked by git. Remove it with `git rm --cached {0}` and add it to .gitignore",
+            path.display()
+        );
+    }
+}
+
+fn is_tracked_by_git(path: &Path) -> bool {
+    let command = Command {
+        command: "git".to_string(),
+        args: vec![
+            "ls-files".to_string(),
+            "--error-unmatch".to_string(),
+            path.display().to_string(),
+        ],
+        ..Default::default()
+    };
+
+    command_handler::run::<Output>(&command, None).is_ok_and(|output| output.status.success())
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    FileHandler(#[from] file_handler::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error("Session Not Found: {}", .0)]
+    NotFound(String),
+
+    #[error("Encryption Error: {}", .0)]
+    Crypto(String),
+
+    #[error("Wrong Passphrase")]
+    WrongPassphrase,
+
+    #[error("Keyring Error: {}", .0)]
+    Keyring(#[source] std::io::Error),
+
+    #[error("Terminal Input Error: {:?}", .0)]
+    Terminal(#[source] std::io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test::temp_dir;
+
+    fn session_data() -> SessionData {
+        SessionData {
+            cookies: Vec::new(),
+            csrf_token: "csrf-token".to_string(),
+            username: Some("user".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_encrypt_and_decrypt() {
+        // Setup
+        let session_data = session_data();
+
+        // Run
+        let encrypted = encrypt(&session_data, "passphrase").unwrap();
+        let decrypted = decrypt(&encrypted, "passphrase").unwrap();
+
+        // Verify
+        assert!(!encrypted.ciphertext.contains("csrf-token"));
+        assert_eq!(session_data.csrf_token, decrypted.csrf_token);
+    }
+
+    #[test]
+    fn test_decrypt_with_wrong_passphrase() {
+        // Setup
+        let encrypted = encrypt(&session_data(), "passphrase").unwrap();
+
+        // Run
+        let result = decrypt(&encrypted, "wrong passphrase");
+
+        // Verify
+        assert!(matches!(result, Err(Error::WrongPassphrase)));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_file_store_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // Setup
+        let dir = temp_dir("file_store_permissions");
+        let created = dir.join("session_data.json");
+        let existing = dir.join("session_data.existing.json");
+        fs::write(&existing, "{}").unwrap();
+        fs::set_permissions(&existing, fs::Permissions::from_mode(0o644)).unwrap();
+
+        // Run
+        FileStore::new(&created).save(&session_data()).unwrap();
+        FileStore::new(&existing).save(&session_data()).unwrap();
+        let loaded = FileStore::new(&created).load().unwrap();
+
+        // Verify
+        let mode = |file| fs::metadata(file).unwrap().permissions().mode() & 0o777;
+        assert_eq!(0o600, mode(&created));
+        assert_eq!(0o600, mode(&existing));
+        assert_eq!("csrf-token", loaded.csrf_token);
+    }
+
+    #[test]
+    fn test_file_store_remove() {
+        // Setup
+        let store = FileStore::new(temp_dir("file_store_remove").join("session_data.json"));
+        store.save(&session_data()).unwrap();
+
+        // Run
+        let removed = store.remove();
+        let removed_again = store.remove();
+
+        // Verify
+        assert!(removed.is_ok());
+        assert!(matches!(removed_again, Err(Error::NotFound(_))));
+    }
+}
@@ -1,14 +1,45 @@
-use crate::dto::Diff;
+use crate::{
+    cli::DiffStyle,
+    dto::{
+        CompileStatus, ContestInfo, Diagnostic, Diff, Severity, TaskSummary, TestCaseResult,
+        TestReport, Verdict,
+    },
+    utils::diff::{self, FirstDifference, Line},
+};
 use itertools::Itertools;
-use std::{io, iter};
+use std::{
+    env,
+    io::{self, IsTerminal},
+    iter,
+};
 use terminal_size::{Height, Width};
+use time::macros::format_description;
 
 pub fn read_credentials() -> io::Result<Credentials> {
-    let username = rprompt::prompt_reply("Username:")?;
-    let password = rpassword::prompt_password("Password:")?;
+    let username = read_username()?;
+    let password = read_password()?;
     Ok(Credentials { username, password })
 }
 
+pub fn read_username() -> io::Result<String> {
+    rprompt::prompt_reply("Username:")
+}
+
+pub fn read_password() -> io::Result<String> {
+    rpassword::prompt_password("Password:")
+}
+
+/// Reads a line from stdin without prompting, for input piped from scripts
+pub fn read_stdin_line() -> io::Result<String> {
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+pub fn read_passphrase() -> io::Result<String> {
+    rpassword::prompt_password("Passphrase:")
+}
+
 pub fn ask_for_retry() -> io::Result<bool> {
     let input = rprompt::prompt_reply("Retry? (y/[n]):")?.to_lowercase();
     Ok(&input == "y" || &input == "yes")
@@ -19,15 +50,230 @@ pub struct Credentials {
     pub password: String,
 }
 
-pub fn print_diffs(diffs: &[Diff], verbose: bool) -> Result<(), Error> {
-    let (Width(w), Height(h)) = terminal_size::terminal_size().ok_or(Error::TerminalSize)?;
+pub fn print_diagnostics(diagnostics: &[Diagnostic]) {
+    for diagnostic in diagnostics {
+        eprintln!("{}", format_diagnostic(diagnostic));
+    }
+}
+
+fn format_diagnostic(
+    Diagnostic {
+        severity,
+        location,
+        message,
+        help,
+    }: &Diagnostic,
+) -> String {
+    let severity = match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    };
+    let location = location
+        .as_ref()
+        .map(|location| {
+            format!(
+                "{}:{}:{}: ",
+                location.file.display(),
+                location.line,
+                location.column
+            )
+        })
+        .unwrap_or_default();
+    let help = help
+        .as_ref()
+        .map(|help| format!("\n  help: {help}"))
+        .unwrap_or_default();
+
+    format!("{severity}: {location}{message}{help}")
+}
+
+pub fn print_diffs<'a>(diffs: impl IntoIterator<Item = &'a Diff>, verbose: bool, style: DiffStyle) {
+    let layout = Layout::detect();
 
     for diff in diffs {
-        let diff = make_diff(diff, verbose, (w as usize, h as usize));
-        println!("{diff}\n");
+        println!("{}\n", make_diff_text(diff, verbose, style, &layout));
     }
+}
 
-    Ok(())
+pub fn print_compile_error(output: &str, verbose: bool) {
+    let layout = Layout::detect();
+
+    println!("{}\n", make_compile_error(output, verbose, &layout));
+}
+
+/// How output is fitted to where it is written
+struct Layout {
+    /// Width and height
+    size: (usize, usize),
+    /// Whether output is trimmed to the size, which is only for a terminal
+    trim: bool,
+    color: bool,
+}
+
+/// Size used where neither the terminal nor `COLUMNS` and `LINES` tell it
+const DEFAULT_SIZE: (usize, usize) = (80, 24);
+
+impl Layout {
+    fn detect() -> Self {
+        let is_terminal = io::stdout().is_terminal();
+        let detected =
+            terminal_size::terminal_size().map(|(Width(w), Height(h))| (w as usize, h as usize));
+        let size = resolve_size(detected, env::var("COLUMNS").ok(), env::var("LINES").ok());
+
+        Self {
+            size,
+            trim: is_terminal,
+            // Colors are disabled by `NO_COLOR` (https://no-color.org)
+            color: is_terminal && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()),
+        }
+    }
+}
+
+/// Size of the terminal, falling back to `COLUMNS` and `LINES`, and then to the default
+fn resolve_size(
+    detected: Option<(usize, usize)>,
+    columns: Option<String>,
+    lines: Option<String>,
+) -> (usize, usize) {
+    let parse = |value: Option<String>| {
+        value
+            .and_then(|value| value.trim().parse::<usize>().ok())
+            .filter(|&value| value > 0)
+    };
+
+    detected
+        .filter(|&(width, height)| width > 0 && height > 0)
+        .unwrap_or_else(|| {
+            (
+                parse(columns).unwrap_or(DEFAULT_SIZE.0),
+                parse(lines).unwrap_or(DEFAULT_SIZE.1),
+            )
+        })
+}
+
+fn make_diff_text(diff: &Diff, verbose: bool, style: DiffStyle, layout: &Layout) -> String {
+    let untrimmed = verbose || !layout.trim;
+    let mut text = match style {
+        DiffStyle::Blocks => make_diff(diff, untrimmed, layout.size),
+        DiffStyle::Unified => make_unified_diff(diff, untrimmed, layout.size, layout.color),
+        DiffStyle::SideBySide => make_side_by_side_diff(diff, untrimmed, layout.size, layout.color),
+    };
+    if let Some(difference) = diff::first_difference(&diff.expected, &diff.actual) {
+        text += &format!("\n{}", format_first_difference(&difference));
+    }
+
+    text
+}
+
+fn make_compile_error(output: &str, verbose: bool, layout: &Layout) -> String {
+    let title = make_title("Compilation", "=", layout.size.0);
+    let output = output.trim_end();
+    let output = if verbose || !layout.trim {
+        output.to_string()
+    } else {
+        trim(output, layout.size)
+    };
+
+    format!("{title}\n{output}")
+}
+
+/// JUnit XML of the test cases, where a compile error is reported as the error of a `compile` case
+pub fn make_junit(
+    TestReport {
+        task,
+        language,
+        compile,
+        test_cases,
+        ..
+    }: &TestReport,
+) -> String {
+    let classname = escape_xml(&format!("{language}.{task}"));
+    let mut cases = Vec::new();
+    let mut failures = 0;
+    let mut errors = 0;
+
+    if compile.status == CompileStatus::Failed {
+        errors += 1;
+        cases.push(format!(
+            "    <testcase name=\"compile\" classname=\"{classname}\">\n{}\n    </testcase>",
+            junit_problem("error", Verdict::CE, &compile.output)
+        ));
+    }
+
+    for TestCaseResult {
+        file,
+        verdict,
+        time_ms,
+        diff,
+        ..
+    } in test_cases
+    {
+        let problem = diff.as_ref().map(|diff| {
+            let kind = if *verdict == Verdict::WA {
+                failures += 1;
+                "failure"
+            } else {
+                errors += 1;
+                "error"
+            };
+            let mut text = format!(
+                "Expected:\n{}\n\nActual:\n{}",
+                diff.expected.trim_end(),
+                diff.actual.trim_end()
+            );
+            if !diff.stderr.trim_end().is_empty() {
+                text += &format!("\n\nStderr:\n{}", diff.stderr.trim_end());
+            }
+            format!("\n{}\n    ", junit_problem(kind, *verdict, &text))
+        });
+        cases.push(format!(
+            "    <testcase name=\"{}\" classname=\"{classname}\" time=\"{:.3}\">{}</testcase>",
+            escape_xml(file),
+            *time_ms as f64 / 1000.0,
+            problem.unwrap_or_default()
+        ));
+    }
+
+    let time = test_cases.iter().map(|case| case.time_ms).sum::<u64>() as f64 / 1000.0;
+    [
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_string(),
+        "<testsuites>".to_string(),
+        format!(
+            "  <testsuite name=\"{classname}\" tests=\"{}\" failures=\"{failures}\" errors=\"{errors}\" time=\"{time:.3}\">",
+            cases.len()
+        ),
+    ]
+    .into_iter()
+    .chain(cases)
+    .chain(["  </testsuite>".to_string(), "</testsuites>".to_string()])
+    .join("\n")
+}
+
+fn junit_problem(kind: &str, verdict: Verdict, text: &str) -> String {
+    let message = match verdict {
+        Verdict::AC => "Accepted",
+        Verdict::WA => "Wrong Answer",
+        Verdict::TLE => "Time Limit Exceeded",
+        Verdict::RE => "Runtime Error",
+        Verdict::CE => "Compilation Error",
+    };
+    format!(
+        "      <{kind} type=\"{verdict}\" message=\"{message}\">{}</{kind}>",
+        escape_xml(text)
+    )
+}
+
+fn escape_xml(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '&' => "&amp;".to_string(),
+            '<' => "&lt;".to_string(),
+            '>' => "&gt;".to_string(),
+            '"' => "&quot;".to_string(),
+            '\'' => "&apos;".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
 }
 
 fn make_diff(
@@ -35,12 +281,13 @@ fn make_diff(
         input,
         expected,
         actual,
+        stderr,
         file,
     }: &Diff,
     verbose: bool,
     terminal_size: (usize, usize),
 ) -> String {
-    let (max_width, max_height) = terminal_size;
+    let (max_width, _) = terminal_size;
 
     let mut lines = Vec::new();
 
@@ -49,21 +296,279 @@ fn make_diff(
     lines.push(title);
 
     for (title, content) in [("Input", input), ("Expected", expected), ("Actual", actual)] {
-        let title = make_title(title, "-", max_width);
-        lines.push(title);
-
-        let content = content.trim_end();
-        let content = if verbose {
-            content.to_string()
-        } else {
-            trim(content, (max_width, max_height))
-        };
-        lines.push(content);
+        lines.extend(make_block(title, content, verbose, terminal_size));
+    }
+    if !stderr.trim_end().is_empty() {
+        lines.extend(make_block("Stderr", stderr, verbose, terminal_size));
+    }
+
+    lines.join("\n")
+}
+
+fn make_block(
+    title: &str,
+    content: &str,
+    verbose: bool,
+    terminal_size: (usize, usize),
+) -> [String; 2] {
+    let title = make_title(title, "-", terminal_size.0);
+
+    let content = content.trim_end();
+    let content = if verbose {
+        content.to_string()
+    } else {
+        trim(content, terminal_size)
+    };
+
+    [title, content]
+}
+
+/// Input, expected and actual output interleaved with `-` and `+`, and stderr
+fn make_unified_diff(
+    Diff {
+        input,
+        expected,
+        actual,
+        stderr,
+        file,
+    }: &Diff,
+    verbose: bool,
+    terminal_size: (usize, usize),
+    color: bool,
+) -> String {
+    let (max_width, max_height) = terminal_size;
+    let width = if verbose {
+        usize::MAX
+    } else {
+        max_width.saturating_sub(2)
+    };
+
+    let diff_lines = diff::diff_lines(expected, actual);
+    let rows = fold_unchanged(&diff_lines, verbose)
+        .into_iter()
+        .flat_map(|line| match line {
+            None => vec![DOT3.to_string()],
+            Some(Line::Same(line)) => vec![format!("  {}", truncate(line, width))],
+            Some(Line::Changed { expected, actual }) => vec![
+                format!(
+                    "- {}",
+                    paint(&truncate(expected, width), Some(actual), RED, color)
+                ),
+                format!(
+                    "+ {}",
+                    paint(&truncate(actual, width), Some(expected), GREEN, color)
+                ),
+            ],
+            Some(Line::Missing(expected)) => {
+                vec![format!(
+                    "- {}",
+                    paint(&truncate(expected, width), None, RED, color)
+                )]
+            }
+            Some(Line::Extra(actual)) => {
+                vec![format!(
+                    "+ {}",
+                    paint(&truncate(actual, width), None, GREEN, color)
+                )]
+            }
+        })
+        .collect_vec();
+
+    make_diff_view(file, input, rows, stderr, verbose, (max_width, max_height))
+}
+
+/// Input, expected and actual output in two columns, and stderr
+fn make_side_by_side_diff(
+    Diff {
+        input,
+        expected,
+        actual,
+        stderr,
+        file,
+    }: &Diff,
+    verbose: bool,
+    terminal_size: (usize, usize),
+    color: bool,
+) -> String {
+    let (max_width, max_height) = terminal_size;
+
+    let diff_lines = diff::diff_lines(expected, actual);
+    let width = if verbose {
+        diff_lines
+            .iter()
+            .map(|line| match line {
+                Line::Same(line) | Line::Missing(line) => line.chars().count(),
+                Line::Changed { expected, .. } => expected.chars().count(),
+                Line::Extra(_) => 0,
+            })
+            .max()
+            .unwrap_or(0)
+    } else {
+        max_width.saturating_sub(3) / 2
+    };
+
+    // Pads by the width of the left column without colors
+    let row = |left: &str, marker: char, painted: (String, String)| {
+        let padding = " ".repeat(width.saturating_sub(left.chars().count()));
+        format!("{}{padding} {marker} {}", painted.0, painted.1)
+            .trim_end()
+            .to_string()
+    };
+    let rows = fold_unchanged(&diff_lines, verbose)
+        .into_iter()
+        .map(|line| match line {
+            None => DOT3.to_string(),
+            Some(Line::Same(line)) => {
+                let line = truncate(line, width);
+                row(&line, ' ', (line.clone(), line.clone()))
+            }
+            Some(Line::Changed { expected, actual }) => {
+                let (left, right) = (truncate(expected, width), truncate(actual, width));
+                let painted = (
+                    paint(&left, Some(actual), RED, color),
+                    paint(&right, Some(expected), GREEN, color),
+                );
+                row(&left, '|', painted)
+            }
+            Some(Line::Missing(expected)) => {
+                let left = truncate(expected, width);
+                let painted = (paint(&left, None, RED, color), String::new());
+                row(&left, '<', painted)
+            }
+            Some(Line::Extra(actual)) => {
+                let right = truncate(actual, width);
+                let painted = (String::new(), paint(&right, None, GREEN, color));
+                row("", '>', painted)
+            }
+        })
+        .collect_vec();
+
+    make_diff_view(file, input, rows, stderr, verbose, (max_width, max_height))
+}
+
+fn make_diff_view(
+    file: &str,
+    input: &str,
+    rows: Vec<String>,
+    stderr: &str,
+    verbose: bool,
+    terminal_size: (usize, usize),
+) -> String {
+    let (max_width, max_height) = terminal_size;
+
+    let rows = if verbose || rows.len() <= max_height {
+        rows
+    } else {
+        let half_height = max_height.saturating_sub(1) >> 1;
+        rows[..half_height]
+            .iter()
+            .cloned()
+            .chain(iter::once(DOT3.to_string()))
+            .chain(rows[rows.len() - half_height..].iter().cloned())
+            .collect()
+    };
+
+    let mut lines = vec![make_title(file, "=", max_width)];
+    lines.extend(make_block("Input", input, verbose, terminal_size));
+    lines.push(make_title("Diff", "-", max_width));
+    lines.extend(rows);
+    if !stderr.trim_end().is_empty() {
+        lines.extend(make_block("Stderr", stderr, verbose, terminal_size));
     }
 
     lines.join("\n")
 }
 
+/// Number of unchanged lines shown around changed ones
+const CONTEXT: usize = 2;
+
+/// Changed lines with unchanged ones around them, where `None` stands for the folded lines
+fn fold_unchanged<'a, 'b>(lines: &'b [Line<'a>], verbose: bool) -> Vec<Option<&'b Line<'a>>> {
+    if verbose {
+        return lines.iter().map(Some).collect();
+    }
+
+    let changed = lines
+        .iter()
+        .positions(|line| !matches!(line, Line::Same(_)))
+        .collect_vec();
+    let is_visible = |i: usize| changed.iter().any(|&j| i.abs_diff(j) <= CONTEXT);
+
+    let mut folded = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if is_visible(i) {
+            folded.push(Some(line));
+        } else if folded.last().is_none_or(Option::is_some) {
+            folded.push(None);
+        }
+    }
+
+    folded
+}
+
+const DOT3: &str = "...";
+
+/// Truncates a line to the width in characters
+fn truncate(line: &str, width: usize) -> String {
+    if line.chars().count() <= width {
+        line.to_string()
+    } else {
+        let prefix = line
+            .chars()
+            .take(width.saturating_sub(DOT3.len()))
+            .collect::<String>();
+        format!("{prefix}{DOT3}")
+    }
+}
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const REVERSE: &str = "\x1b[7m";
+const NO_REVERSE: &str = "\x1b[27m";
+const RESET: &str = "\x1b[0m";
+
+/// Colors a line, with the tokens differing from the other line in reverse video
+fn paint(line: &str, other: Option<&str>, style: &str, color: bool) -> String {
+    if !color || line.is_empty() {
+        return line.to_string();
+    }
+
+    let body = match other {
+        Some(other) => diff::mark_tokens(line, other)
+            .into_iter()
+            .map(|(segment, differs)| {
+                if differs {
+                    format!("{REVERSE}{segment}{NO_REVERSE}")
+                } else {
+                    segment.to_string()
+                }
+            })
+            .collect(),
+        None => line.to_string(),
+    };
+
+    format!("{style}{body}{RESET}")
+}
+
+fn format_first_difference(
+    FirstDifference {
+        line,
+        token,
+        expected,
+        actual,
+    }: &FirstDifference,
+) -> String {
+    let describe = |token: &Option<&str>| match token {
+        Some(token) => format!("`{token}`"),
+        None => "end of output".to_string(),
+    };
+    format!(
+        "First difference at line {line}, token {token}: expected {}, actual {}",
+        describe(expected),
+        describe(actual)
+    )
+}
+
 fn make_title(title: &str, style: &str, max_width: usize) -> String {
     format!("{:^width$}", title, width = max_width).replace(' ', style)
 }
@@ -72,8 +577,8 @@ fn trim(text: &str, (max_width, max_height): (usize, usize)) -> String {
     let lines = text.lines().collect_vec();
 
     let dot3 = "...";
-    let left_width = (max_width - dot3.len()) >> 1;
-    let right_width = (max_width - dot3.len()) - left_width;
+    let left_width = max_width.saturating_sub(dot3.len()) >> 1;
+    let right_width = max_width.saturating_sub(dot3.len()) - left_width;
 
     let height_trimmed_lines = if lines.len() <= max_height {
         lines
@@ -106,15 +611,102 @@ fn trim(text: &str, (max_width, max_height): (usize, usize)) -> String {
     trimmed_text
 }
 
+pub fn print_contest_info(
+    ContestInfo {
+        title,
+        start_time,
+        end_time,
+        duration_minutes,
+        rated_range,
+        penalty,
+        tasks,
+    }: &ContestInfo,
+) -> Result<(), Error> {
+    let format = format_description!(
+        "[year]-[month]-[day] [hour]:[minute] [offset_hour sign:mandatory]:[offset_minute]"
+    );
+    let none = "-".to_string();
+
+    let properties = [
+        vec!["Start:".to_string(), start_time.format(format)?],
+        vec!["End:".to_string(), end_time.format(format)?],
+        vec![
+            "Duration:".to_string(),
+            format!("{duration_minutes} minutes"),
+        ],
+        vec![
+            "Rated Range:".to_string(),
+            rated_range.clone().unwrap_or_else(|| none.clone()),
+        ],
+        vec!["Penalty:".to_string(), penalty.clone().unwrap_or(none)],
+    ];
+
+    println!("{title}");
+    println!("{}\n", make_table(&properties));
+    println!("{}", make_task_table(tasks));
+
+    Ok(())
+}
+
+fn make_task_table(tasks: &[TaskSummary]) -> String {
+    let header = ["Task", "Score", "Time Limit", "Memory Limit", "Name"].map(Into::into);
+
+    let rows = tasks.iter().map(
+        |TaskSummary {
+             label,
+             name,
+             score,
+             time_limit,
+             memory_limit,
+             ..
+         }| {
+            vec![
+                label.clone(),
+                score.map_or("-".to_string(), |score| score.to_string()),
+                time_limit.clone(),
+                memory_limit.clone(),
+                name.clone(),
+            ]
+        },
+    );
+
+    make_table(&iter::once(header.to_vec()).chain(rows).collect_vec())
+}
+
+fn make_table(rows: &[Vec<String>]) -> String {
+    let column_count = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths = (0..column_count)
+        .map(|i| {
+            rows.iter()
+                .filter_map(|row| row.get(i))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect_vec();
+
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .join("\n")
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
-    #[error("Failed to get terminal size")]
-    TerminalSize,
+    #[error(transparent)]
+    TimeFormat(#[from] time::error::Format),
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::dto::CompileResult;
 
     #[test]
     fn test_make_title() {
@@ -122,12 +714,69 @@ mod tests {
         assert_eq!("==1.txt===", title)
     }
 
+    #[test]
+    fn test_make_junit() {
+        // Setup
+        let test_case = |file: &str, verdict, diff| TestCaseResult {
+            file: file.to_string(),
+            verdict,
+            time_ms: 1500,
+            memory_kib: Some(1024),
+            diff,
+        };
+        let report = TestReport {
+            task: "A".to_string(),
+            language: "cpp".to_string(),
+            verdict: Verdict::WA,
+            compile: CompileResult {
+                status: CompileStatus::Cached,
+                output: String::new(),
+            },
+            test_cases: vec![
+                test_case("1.txt", Verdict::AC, None),
+                test_case(
+                    "2.txt",
+                    Verdict::WA,
+                    Some(Diff {
+                        input: "1".to_string(),
+                        expected: "a<b\n".to_string(),
+                        actual: "a&b\n".to_string(),
+                        stderr: String::new(),
+                        file: "2.txt".to_string(),
+                    }),
+                ),
+            ],
+        };
+
+        // Run
+        let junit = make_junit(&report);
+
+        // Verify
+        assert_eq!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites>
+  <testsuite name="cpp.A" tests="2" failures="1" errors="0" time="3.000">
+    <testcase name="1.txt" classname="cpp.A" time="1.500"></testcase>
+    <testcase name="2.txt" classname="cpp.A" time="1.500">
+      <failure type="WA" message="Wrong Answer">Expected:
+a&lt;b
+
+Actual:
+a&amp;b</failure>
+    </testcase>
+  </testsuite>
+</testsuites>"#,
+            junit
+        );
+    }
+
     #[test]
     fn test_make_diff() {
         let diff = Diff {
             input: "1 2\n3\n".to_string(),
             expected: "4 5\n6\n".to_string(),
             actual: "7 8\n9\n".to_string(),
+            stderr: String::new(),
             file: "test.txt".to_string(),
         };
         let verbose = true;
@@ -155,6 +804,7 @@ mod tests {
             input: "".to_string(),
             expected: " ".to_string(),
             actual: "\n".to_string(),
+            stderr: String::new(),
             file: "test.txt".to_string(),
         };
         let verbose = true;
@@ -179,6 +829,7 @@ mod tests {
             input: "a bc def hjk\n".to_string(),
             expected: "l\n".to_string(),
             actual: "m n o p q    \n\n\nr\n".to_string(),
+            stderr: String::new(),
             file: "test.txt".to_string(),
         };
         let verbose = false;
@@ -199,6 +850,183 @@ r",
         );
     }
 
+    #[test]
+    fn test_make_unified_diff() {
+        // Setup
+        let diff = Diff {
+            input: "3\n".to_string(),
+            expected: "1\n2\n3 4\n5\n6\n7\n8\n9\n10\n".to_string(),
+            actual: "1\n2\n3 5\n5\n6\n7\n8\n9\n".to_string(),
+            stderr: "warning\n".to_string(),
+            file: "test.txt".to_string(),
+        };
+
+        // Run
+        let output = make_unified_diff(&diff, false, (14, 20), false);
+        let colored = make_unified_diff(&diff, false, (14, 20), true);
+
+        // Verify
+        assert_eq!(
+            "\
+===test.txt===
+----Input-----
+3
+-----Diff-----
+  1
+  2
+- 3 4
++ 3 5
+  5
+  6
+...
+  8
+  9
+- 10
+----Stderr----
+warning",
+            output
+        );
+        assert!(colored.contains("- \x1b[31m3 \x1b[7m4\x1b[27m\x1b[0m\n"));
+        assert!(colored.contains("- \x1b[31m10\x1b[0m\n"));
+    }
+
+    #[test]
+    fn test_make_side_by_side_diff() {
+        // Setup
+        let diff = Diff {
+            input: "".to_string(),
+            expected: "1 2\nabcdefg\n".to_string(),
+            actual: "1 3\nabcdefg\nh\n".to_string(),
+            stderr: String::new(),
+            file: "test.txt".to_string(),
+        };
+
+        // Run
+        let output = make_side_by_side_diff(&diff, false, (15, 20), false);
+
+        // Verify
+        assert_eq!(
+            "\
+===test.txt====
+-----Input-----
+
+-----Diff------
+1 2    | 1 3
+abc...   abc...
+       > h",
+            output
+        );
+    }
+
+    #[test]
+    fn test_format_first_difference() {
+        // Setup
+        let difference = FirstDifference {
+            line: 2,
+            token: 1,
+            expected: Some("4"),
+            actual: None,
+        };
+
+        // Run
+        let message = format_first_difference(&difference);
+
+        // Verify
+        assert_eq!(
+            "First difference at line 2, token 1: expected `4`, actual end of output",
+            message
+        );
+    }
+
+    #[test]
+    fn test_resolve_size() {
+        // Run
+        let detected = resolve_size(Some((100, 50)), Some("40".to_string()), None);
+        let env = resolve_size(None, Some("40".to_string()), Some(" 10 ".to_string()));
+        let default = resolve_size(
+            Some((0, 0)),
+            Some("wide".to_string()),
+            Some("0".to_string()),
+        );
+
+        // Verify
+        assert_eq!((100, 50), detected);
+        assert_eq!((40, 10), env);
+        assert_eq!(DEFAULT_SIZE, default);
+    }
+
+    #[test]
+    fn test_make_diff_text_without_terminal() {
+        // Setup
+        let diff = Diff {
+            input: "a bc def hjk\n".to_string(),
+            expected: "l\n".to_string(),
+            actual: "m n o p q\n\n\nr\n".to_string(),
+            stderr: String::new(),
+            file: "test.txt".to_string(),
+        };
+        let layout = Layout {
+            size: (10, 3),
+            trim: false,
+            color: false,
+        };
+
+        // Run
+        let output = make_diff_text(&diff, false, DiffStyle::Blocks, &layout);
+
+        // Verify
+        assert_eq!(
+            "\
+=test.txt=
+--Input---
+a bc def hjk
+-Expected-
+l
+--Actual--
+m n o p q
+
+
+r
+First difference at line 1, token 1: expected `l`, actual `m`",
+            output
+        );
+    }
+
+    #[test]
+    fn test_make_compile_error_without_terminal() {
+        // Setup
+        let output = "error: 1\nerror: 2\nerror: 3\nerror: 4\n";
+        let layout = Layout {
+            size: (10, 3),
+            trim: false,
+            color: false,
+        };
+
+        // Run
+        let text = make_compile_error(output, false, &layout);
+
+        // Verify
+        assert_eq!("Compilation\nerror: 1\nerror: 2\nerror: 3\nerror: 4", text);
+    }
+
+    #[test]
+    fn test_make_table() {
+        let rows = [
+            vec!["Task".to_string(), "Score".to_string(), "Name".to_string()],
+            vec!["A".to_string(), "100".to_string(), "Full House".to_string()],
+            vec!["Ex".to_string(), "-".to_string(), "Hard".to_string()],
+        ];
+
+        let table = make_table(&rows);
+        assert_eq!(
+            "\
+Task  Score  Name
+A     100    Full House
+Ex    -      Hard",
+            table
+        );
+    }
+
     #[test]
     fn test_trim() {
         let text = "a b\nc\nd e\nf\n\ngh i jk  \n";
@@ -0,0 +1,369 @@
+use super::{
+    file_handler::{self, WithPath},
+    http_handler::Error,
+};
+use crate::dto::{config::HttpConfig, cookie::IntoCookieStore, Exchange};
+use cookie_store::Cookie;
+use regex::Regex;
+use std::{
+    cell::RefCell,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use ureq::Agent;
+
+const REDACTED: &str = "<redacted>";
+const SECRET_FIELDS: [&str; 2] = ["password", "csrf_token"];
+const SECRET_HEADERS: [&str; 1] = ["set-cookie"];
+
+/// Sends HTTP requests on behalf of `HttpHandler`
+///
+/// Responses are returned for every status code; interpreting them is up to the caller.
+pub trait Transport {
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, Error>;
+    fn post_form(&self, url: &str, form: &[(&str, &str)]) -> Result<Response, Error>;
+    fn cookies(&self) -> Vec<Cookie<'static>>;
+}
+
+pub struct Response {
+    pub status: u16,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Response {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+pub struct UreqTransport {
+    agent: Agent,
+}
+
+impl UreqTransport {
+    pub fn new(config: &HttpConfig, cookies: Vec<Cookie<'static>>) -> Self {
+        let agent = ureq::builder()
+            .timeout_connect(Duration::from_secs(config.connect_timeout_secs))
+            .timeout_read(Duration::from_secs(config.read_timeout_secs))
+            .cookie_store(cookies.into_cookie_store())
+            .build();
+        Self { agent }
+    }
+}
+
+impl Transport for UreqTransport {
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, Error> {
+        let request = headers
+            .iter()
+            .fold(self.agent.get(url), |request, (name, value)| {
+                request.set(name, value)
+            });
+        into_response(request.call())
+    }
+
+    fn post_form(&self, url: &str, form: &[(&str, &str)]) -> Result<Response, Error> {
+        into_response(self.agent.post(url).send_form(form))
+    }
+
+    fn cookies(&self) -> Vec<Cookie<'static>> {
+        self.agent
+            .cookie_store()
+            .iter_unexpired()
+            .cloned()
+            .collect()
+    }
+}
+
+/// Transport recording every exchange of the inner transport into a fixture file
+pub struct RecordingTransport<T> {
+    inner: T,
+    file: PathBuf,
+    exchanges: RefCell<Vec<Exchange>>,
+}
+
+impl<T: Transport> RecordingTransport<T> {
+    pub fn new(inner: T, file: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            file: file.into(),
+            exchanges: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn record(
+        &self,
+        method: &str,
+        url: &str,
+        form: &[(&str, &str)],
+        response: Result<Response, Error>,
+    ) -> Result<Response, Error> {
+        let response = response?;
+
+        let form = form
+            .iter()
+            .map(|&(name, value)| (name.to_string(), redact(&SECRET_FIELDS, name, value)))
+            .collect();
+        let headers = response
+            .headers
+            .iter()
+            .map(|(name, value)| (name.clone(), redact(&SECRET_HEADERS, name, value)))
+            .collect();
+
+        let mut exchanges = self.exchanges.borrow_mut();
+        exchanges.push(Exchange {
+            method: method.to_string(),
+            url: url.to_string(),
+            form,
+            status: response.status,
+            headers,
+            body: scrub(&response.body),
+        });
+
+        // Saved after every exchange so that an interrupted run still leaves a fixture
+        if let Some(dir) = self.file.parent() {
+            fs::create_dir_all(dir).with_path(dir)?;
+        }
+        file_handler::save(&self.file, &*exchanges)?;
+
+        Ok(response)
+    }
+}
+
+impl<T: Transport> Transport for RecordingTransport<T> {
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, Error> {
+        self.record("GET", url, &[], self.inner.get(url, headers))
+    }
+
+    fn post_form(&self, url: &str, form: &[(&str, &str)]) -> Result<Response, Error> {
+        self.record("POST", url, form, self.inner.post_form(url, form))
+    }
+
+    fn cookies(&self) -> Vec<Cookie<'static>> {
+        self.inner.cookies()
+    }
+}
+
+fn redact(secrets: &[&str], name: &str, value: &str) -> String {
+    if secrets
+        .iter()
+        .any(|secret| secret.eq_ignore_ascii_case(name))
+    {
+        REDACTED.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Redacts the CSRF token and the username, which are bound to the session, from a response body
+fn scrub(body: &str) -> String {
+    let script = Regex::new(r#"(var (?:csrfToken|userScreenName) = ")([^"]*)(")"#).unwrap();
+    let input = Regex::new(r#"<input[^>]*name="csrf_token"[^>]*>"#).unwrap();
+    let value = Regex::new(r#"(value=")([^"]*)(")"#).unwrap();
+
+    let username = Regex::new(r#"var userScreenName = "([^"]+)""#)
+        .unwrap()
+        .captures(body)
+        .map(|captures| regex::escape(&captures[1]));
+
+    let body = script.replace_all(body, format!("${{1}}{REDACTED}${{3}}"));
+    let body = input.replace_all(&body, |captures: &regex::Captures| {
+        value
+            .replace(&captures[0], format!("${{1}}{REDACTED}${{3}}"))
+            .into_owned()
+    });
+
+    let Some(username) = username else {
+        return body.into_owned();
+    };
+
+    // The username also appears in links to the profile and in the user menu of the header
+    let profile = Regex::new(&format!(r#"(/users/){username}(["/?#])"#)).unwrap();
+    let user_menu = Regex::new(&format!(r#"(</span> ){username}( <span class="caret">)"#)).unwrap();
+
+    let body = profile.replace_all(&body, format!("${{1}}{REDACTED}${{2}}"));
+    let body = user_menu.replace_all(&body, format!("${{1}}{REDACTED}${{2}}"));
+    body.into_owned()
+}
+
+/// Transport replaying exchanges recorded by `RecordingTransport`
+///
+/// Exchanges for the same method and URL are replayed in recorded order, and the last one is repeated.
+pub struct ReplayTransport {
+    exchanges: Vec<Exchange>,
+    replayed: RefCell<Vec<bool>>,
+}
+
+impl ReplayTransport {
+    pub fn load(file: &Path) -> Result<Self, file_handler::Error> {
+        let exchanges: Vec<Exchange> = file_handler::load(file)?;
+        let replayed = RefCell::new(vec![false; exchanges.len()]);
+        Ok(Self {
+            exchanges,
+            replayed,
+        })
+    }
+
+    fn replay(&self, method: &str, url: &str) -> Result<Response, Error> {
+        let candidates = self
+            .exchanges
+            .iter()
+            .enumerate()
+            .filter(|(_, exchange)| exchange.method == method && exchange.url == url)
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+
+        let mut replayed = self.replayed.borrow_mut();
+        let i = candidates
+            .iter()
+            .copied()
+            .find(|&i| !replayed[i])
+            .or(candidates.last().copied())
+            .ok_or_else(|| Error::NotRecorded {
+                method: method.to_string(),
+                url: url.to_string(),
+            })?;
+        replayed[i] = true;
+
+        let exchange = &self.exchanges[i];
+        Ok(Response {
+            status: exchange.status,
+            url: exchange.url.clone(),
+            headers: exchange.headers.clone(),
+            body: exchange.body.clone(),
+        })
+    }
+}
+
+impl Transport for ReplayTransport {
+    fn get(&self, url: &str, _: &[(&str, &str)]) -> Result<Response, Error> {
+        self.replay("GET", url)
+    }
+
+    fn post_form(&self, url: &str, _: &[(&str, &str)]) -> Result<Response, Error> {
+        self.replay("POST", url)
+    }
+
+    fn cookies(&self) -> Vec<Cookie<'static>> {
+        Vec::new()
+    }
+}
+
+fn into_response(result: Result<ureq::Response, ureq::Error>) -> Result<Response, Error> {
+    let response = match result {
+        Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+        Err(error) => return Err(error.into()),
+    };
+
+    let headers = response
+        .headers_names()
+        .into_iter()
+        .filter_map(|name| {
+            let value = response.header(&name)?.to_string();
+            Some((name, value))
+        })
+        .collect();
+
+    Ok(Response {
+        status: response.status(),
+        url: response.get_url().to_string(),
+        headers,
+        body: response.into_string()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test::{temp_dir, FixtureTransport};
+
+    #[test]
+    fn test_header() {
+        // Setup
+        let response = Response {
+            status: 200,
+            url: "https://atcoder.jp".to_string(),
+            headers: vec![("etag".to_string(), "\"abc\"".to_string())],
+            body: String::new(),
+        };
+
+        // Run
+        let etag = response.header("ETag");
+
+        // Verify
+        assert_eq!(Some("\"abc\""), etag);
+    }
+
+    #[test]
+    fn test_scrub() {
+        // Setup
+        let body = [
+            r#"<script>var userScreenName = "a"; var csrfToken = "token";</script>"#,
+            r#"<a class="dropdown-toggle"><span class="glyphicon glyphicon-cog"></span> a <span class="caret"></span></a>"#,
+            r#"<a href="/users/a">My Profile</a><a href="/users/a/history">History</a>"#,
+            r#"<a href="/users/ab">ab</a><a href="/contests/abc388/tasks/abc388_a">A - a</a>"#,
+        ]
+        .join("\n");
+
+        // Run
+        let scrubbed = scrub(&body);
+
+        // Verify
+        assert_eq!(
+            [
+                r#"<script>var userScreenName = "<redacted>"; var csrfToken = "<redacted>";</script>"#,
+                r#"<a class="dropdown-toggle"><span class="glyphicon glyphicon-cog"></span> <redacted> <span class="caret"></span></a>"#,
+                r#"<a href="/users/<redacted>">My Profile</a><a href="/users/<redacted>/history">History</a>"#,
+                r#"<a href="/users/ab">ab</a><a href="/contests/abc388/tasks/abc388_a">A - a</a>"#,
+            ]
+            .join("\n"),
+            scrubbed
+        );
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        // Setup
+        let file = &temp_dir("record_and_replay").join("cassette.json");
+        let inner = FixtureTransport::new()
+            .route_get("https://atcoder.jp/home", "homepage.html")
+            .route_post("https://atcoder.jp/login", "homepage_logged_in.html");
+        let recorder = RecordingTransport::new(inner, file);
+        let form = [("username", "user"), ("password", "secret")];
+
+        // Run
+        recorder.get("https://atcoder.jp/home", &[]).unwrap();
+        recorder
+            .post_form("https://atcoder.jp/login", &form)
+            .unwrap();
+        let replayer = ReplayTransport::load(file).unwrap();
+
+        // Verify
+        let recorded: Vec<Exchange> = file_handler::load(file).unwrap();
+        assert_eq!(2, recorded.len());
+        assert_eq!(
+            vec![
+                ("username".to_string(), "user".to_string()),
+                ("password".to_string(), REDACTED.to_string())
+            ],
+            recorded[1].form
+        );
+
+        for exchange in &recorded {
+            assert!(!exchange.body.contains("fixture-csrf-token"));
+            assert!(!exchange.body.contains("fixture_user"));
+        }
+
+        let homepage = replayer.get("https://atcoder.jp/home", &[]).unwrap();
+        assert!(homepage.body.contains("Sign Up"));
+        assert!(matches!(
+            replayer.get("https://atcoder.jp/missing", &[]),
+            Err(Error::NotRecorded { .. })
+        ));
+    }
+}
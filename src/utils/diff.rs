@@ -0,0 +1,234 @@
+//! Differences between expected and actual output, where whitespace is ignored as the judge does
+
+use itertools::{EitherOrBoth, Itertools};
+use std::mem;
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum Line<'a> {
+    Same(&'a str),
+    Changed {
+        expected: &'a str,
+        actual: &'a str,
+    },
+    /// Only in the expected output
+    Missing(&'a str),
+    /// Only in the actual output
+    Extra(&'a str),
+}
+
+/// Size of the LCS table above which lines are paired by index
+const MAX_LCS_CELLS: usize = 1 << 22;
+
+pub fn diff_lines<'a>(expected: &'a str, actual: &'a str) -> Vec<Line<'a>> {
+    let expected = expected.trim_end().lines().collect_vec();
+    let actual = actual.trim_end().lines().collect_vec();
+
+    if expected.len() == actual.len()
+        || (expected.len() + 1).saturating_mul(actual.len() + 1) > MAX_LCS_CELLS
+    {
+        diff_by_index(&expected, &actual)
+    } else {
+        diff_by_lcs(&expected, &actual)
+    }
+}
+
+fn diff_by_index<'a>(expected: &[&'a str], actual: &[&'a str]) -> Vec<Line<'a>> {
+    expected
+        .iter()
+        .zip_longest(actual)
+        .map(|pair| match pair.map_any(|line| *line, |line| *line) {
+            EitherOrBoth::Both(expected, actual) if is_same(expected, actual) => Line::Same(actual),
+            EitherOrBoth::Both(expected, actual) => Line::Changed { expected, actual },
+            EitherOrBoth::Left(expected) => Line::Missing(expected),
+            EitherOrBoth::Right(actual) => Line::Extra(actual),
+        })
+        .collect()
+}
+
+fn diff_by_lcs<'a>(expected: &[&'a str], actual: &[&'a str]) -> Vec<Line<'a>> {
+    let (n, m) = (expected.len(), actual.len());
+
+    // Length of the LCS of the suffixes
+    let mut lengths = vec![vec![0_u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i][j] = if is_same(expected[i], actual[j]) {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let mut missing = Vec::new();
+    let mut extra = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && is_same(expected[i], actual[j]) {
+            pair_up(&mut lines, &mut missing, &mut extra);
+            lines.push(Line::Same(actual[j]));
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || lengths[i][j + 1] >= lengths[i + 1][j]) {
+            extra.push(actual[j]);
+            j += 1;
+        } else {
+            missing.push(expected[i]);
+            i += 1;
+        }
+    }
+    pair_up(&mut lines, &mut missing, &mut extra);
+
+    lines
+}
+
+/// Pairs the lines removed and added at the same place as changed ones
+fn pair_up<'a>(lines: &mut Vec<Line<'a>>, missing: &mut Vec<&'a str>, extra: &mut Vec<&'a str>) {
+    lines.extend(diff_by_index(&mem::take(missing), &mem::take(extra)));
+}
+
+fn is_same(expected: &str, actual: &str) -> bool {
+    expected.split_whitespace().eq(actual.split_whitespace())
+}
+
+/// First token differing between expected and actual output, at 1-based positions of the expected one
+///
+/// A token is `None` where the output has ended.
+#[derive(PartialEq, Eq, Debug)]
+pub struct FirstDifference<'a> {
+    pub line: usize,
+    pub token: usize,
+    pub expected: Option<&'a str>,
+    pub actual: Option<&'a str>,
+}
+
+pub fn first_difference<'a>(expected: &'a str, actual: &'a str) -> Option<FirstDifference<'a>> {
+    let tokens = |text: &'a str| {
+        text.lines().enumerate().flat_map(|(i, line)| {
+            line.split_whitespace()
+                .enumerate()
+                .map(move |(j, token)| (i + 1, j + 1, token))
+        })
+    };
+
+    tokens(expected)
+        .zip_longest(tokens(actual))
+        .find(|pair| pair.as_ref().both().is_none_or(|(e, a)| e.2 != a.2))
+        .map(|pair| {
+            let (expected, actual) = pair.left_and_right();
+            let (line, token, _) = expected.or(actual).unwrap();
+            FirstDifference {
+                line,
+                token,
+                expected: expected.map(|(_, _, token)| token),
+                actual: actual.map(|(_, _, token)| token),
+            }
+        })
+}
+
+/// Splits a line into tokens and the whitespace between them, marking tokens which differ from
+/// the token at the same index of the other line
+pub fn mark_tokens<'a>(line: &'a str, other: &str) -> Vec<(&'a str, bool)> {
+    let mut other_tokens = other.split_whitespace();
+    let mut segments = Vec::new();
+    let mut rest = line;
+
+    while !rest.is_empty() {
+        let is_space = rest.starts_with(char::is_whitespace);
+        let end = rest
+            .find(|c: char| c.is_whitespace() != is_space)
+            .unwrap_or(rest.len());
+        let (segment, tail) = rest.split_at(end);
+        let differs = !is_space && other_tokens.next() != Some(segment);
+        segments.push((segment, differs));
+        rest = tail;
+    }
+
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_lines() {
+        // Setup
+        let expected = "1\n2 3\n4\n5\n";
+        let actual = "1\n2  4\n5\n6\n7";
+
+        // Run
+        let lines = diff_lines(expected, actual);
+
+        // Verify
+        assert_eq!(
+            vec![
+                Line::Same("1"),
+                Line::Changed {
+                    expected: "2 3",
+                    actual: "2  4"
+                },
+                Line::Missing("4"),
+                Line::Same("5"),
+                Line::Extra("6"),
+                Line::Extra("7"),
+            ],
+            lines
+        );
+    }
+
+    #[test]
+    fn test_first_difference() {
+        // Setup
+        let cases = [
+            ("1 2\n3 4", "1 2\n3 5"),
+            ("1 2\n3", "1 2 3 4"),
+            ("1\n", " 1 \n\n"),
+        ];
+
+        // Run
+        let [changed, extra, same] =
+            cases.map(|(expected, actual)| first_difference(expected, actual));
+
+        // Verify
+        assert_eq!(
+            Some(FirstDifference {
+                line: 2,
+                token: 2,
+                expected: Some("4"),
+                actual: Some("5")
+            }),
+            changed
+        );
+        assert_eq!(
+            Some(FirstDifference {
+                line: 1,
+                token: 4,
+                expected: None,
+                actual: Some("4")
+            }),
+            extra
+        );
+        assert_eq!(None, same);
+    }
+
+    #[test]
+    fn test_mark_tokens() {
+        // Run
+        let segments = mark_tokens(" 1  3 4", "1 2");
+
+        // Verify
+        assert_eq!(
+            vec![
+                (" ", false),
+                ("1", false),
+                ("  ", false),
+                ("3", true),
+                (" ", false),
+                ("4", true)
+            ],
+            segments
+        );
+    }
+}
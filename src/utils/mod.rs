@@ -1,2 +1,4 @@
+pub mod diff;
+pub mod suggest;
 #[cfg(test)]
 pub mod test;
@@ -0,0 +1,54 @@
+/// Candidate closest to a misspelled name, if any is close enough to be a likely typo
+pub fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let max_distance = (name.chars().count() / 3).max(1);
+
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|&(distance, _)| distance <= max_distance)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance counted in chars
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, &b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != b);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(0, edit_distance("rust", "rust"));
+        assert_eq!(2, edit_distance("rsut", "rust"));
+        assert_eq!(3, edit_distance("", "cpp"));
+    }
+
+    #[test]
+    fn test_closest() {
+        // Setup
+        let candidates = ["rust", "cpp", "cpp-clang", "python"];
+
+        // Run
+        let typo = closest("pyhton", candidates);
+        let unrelated = closest("haskell", candidates);
+
+        // Verify
+        assert_eq!(Some("python"), typo);
+        assert_eq!(None, unrelated);
+    }
+}
@@ -1,8 +1,17 @@
 use crate::{
     domain::{html::Html, page_type},
-    dto::SessionData,
+    dto::{config::HttpConfig, SessionData},
+    handler::{
+        http_handler::{self, HttpHandler},
+        transport::{Response, Transport},
+    },
+};
+use cookie_store::Cookie;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
 };
-use std::{fs, path::Path};
 use ureq::Agent;
 
 #[derive(Debug)]
@@ -20,12 +29,6 @@ pub fn load_homepage_html() -> Html<page_type::Home> {
     load_html::<page_type::Home>(file, url)
 }
 
-pub fn load_contest_page_html() -> Html<page_type::ContestHome> {
-    let file = "tests/data/contest_page.html";
-    let url = "https://atcoder.jp/contests/abc386";
-    load_html::<page_type::ContestHome>(file, url)
-}
-
 pub fn load_tasks_html() -> Html<page_type::Tasks> {
     let file = "tests/data/tasks.html";
     let url = "https://atcoder.jp/contests/abc386/tasks";
@@ -108,9 +111,150 @@ fn fetch_session_data(url: &str) -> Result<SessionData, Error> {
     Ok(SessionData {
         cookies,
         csrf_token: "Dummy CSRF Token".to_string(),
+        username: None,
     })
 }
 
+/// Set to record cassettes from the network with the given session instead of replaying them
+const RECORD_ENV: &str = "RECORD_CASSETTES";
+
+/// HTTP handler replaying `tests/data/cassettes/<name>.json`
+///
+/// With `RECORD_CASSETTES` set, the cassette is recorded from the network with the cookies instead.
+pub fn load_cassette(name: &str, cookies: impl FnOnce() -> Vec<Cookie<'static>>) -> HttpHandler {
+    let file = PathBuf::from(format!("tests/data/cassettes/{name}.json"));
+
+    let mut config = HttpConfig {
+        cache: false,
+        ..Default::default()
+    };
+    if std::env::var_os(RECORD_ENV).is_some() {
+        println!("Recording {}", file.display());
+        config.record = Some(file);
+        return HttpHandler::with_cookies(cookies(), &config)
+            .expect("Error: Fail to record cassette");
+    }
+
+    assert!(
+        file.exists(),
+        "Cassette Not Found: {}. Record it with {RECORD_ENV}=1",
+        file.display()
+    );
+    config.replay = Some(file);
+    HttpHandler::with_cookies(Vec::new(), &config).expect("Error: Fail to load cassette")
+}
+
+/// Transport replaying responses from files under `tests/data/fixtures`
+#[derive(Default)]
+pub struct FixtureTransport {
+    routes: HashMap<(Method, String), PathBuf>,
+    cookies: Vec<Cookie<'static>>,
+    rejected_csrf_token: Option<String>,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+enum Method {
+    Get,
+    Post,
+}
+
+impl FixtureTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn route_get(mut self, url: &str, fixture: &str) -> Self {
+        self.routes
+            .insert((Method::Get, url.to_string()), fixture_path(fixture));
+        self
+    }
+
+    pub fn route_post(mut self, url: &str, fixture: &str) -> Self {
+        self.routes
+            .insert((Method::Post, url.to_string()), fixture_path(fixture));
+        self
+    }
+
+    /// Answers 403 to POSTs with the given CSRF token, as AtCoder does to a stale one
+    pub fn reject_csrf_token(mut self, csrf_token: &str) -> Self {
+        self.rejected_csrf_token = Some(csrf_token.to_string());
+        self
+    }
+
+    pub fn into_http_handler(self) -> HttpHandler {
+        let config = HttpConfig {
+            min_interval_ms: 0,
+            cache: false,
+            ..Default::default()
+        };
+        HttpHandler::with_transport(Box::new(self), &config)
+    }
+
+    fn respond(&self, method: Method, url: &str) -> Result<Response, http_handler::Error> {
+        let response = match self.routes.get(&(method, url.to_string())) {
+            Some(file) => Response {
+                status: 200,
+                url: url.to_string(),
+                headers: Vec::new(),
+                body: fs::read_to_string(file)?,
+            },
+            None => Response {
+                status: 404,
+                url: url.to_string(),
+                headers: Vec::new(),
+                body: String::new(),
+            },
+        };
+
+        Ok(response)
+    }
+}
+
+impl Transport for FixtureTransport {
+    fn get(&self, url: &str, _: &[(&str, &str)]) -> Result<Response, http_handler::Error> {
+        self.respond(Method::Get, url)
+    }
+
+    fn post_form(&self, url: &str, form: &[(&str, &str)]) -> Result<Response, http_handler::Error> {
+        let rejected = form.iter().any(|&(name, value)| {
+            name == "csrf_token" && Some(value) == self.rejected_csrf_token.as_deref()
+        });
+
+        if rejected {
+            return Ok(Response {
+                status: 403,
+                url: url.to_string(),
+                headers: Vec::new(),
+                body: String::new(),
+            });
+        }
+
+        self.respond(Method::Post, url)
+    }
+
+    fn cookies(&self) -> Vec<Cookie<'static>> {
+        self.cookies.clone()
+    }
+}
+
+/// Empty directory under the system temp dir, unique to the test name and the process
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("atcoder-tools-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("Error: Fail to create temp dir");
+    dir
+}
+
+pub fn load_fixture_html<PageType>(fixture: &str) -> Html<PageType> {
+    fs::read_to_string(fixture_path(fixture))
+        .expect("Fixture Not Found")
+        .into()
+}
+
+fn fixture_path(fixture: &str) -> PathBuf {
+    Path::new("tests/data/fixtures").join(fixture)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;